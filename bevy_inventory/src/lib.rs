@@ -135,6 +135,16 @@ impl Inventory {
         self.items.iter().filter_map(|i| *i)
     }
 
+    /// every slot in order, empty ones included
+    pub fn slots(&self) -> &[Option<Entity>] {
+        &self.items
+    }
+
+    /// inventory with the slots as they are (see [`Inventory::slots`])
+    pub fn from_slots(slots: Vec<Option<Entity>>) -> Self {
+        Self { items: slots }
+    }
+
     pub fn is_full(&self) -> bool {
         self.items.iter().all(|i| i.is_some())
     }
@@ -1,6 +1,7 @@
 use super::{ActionCost, AttributeType, Attributes, Damage, DamageKind, Rate};
 use bevy::{prelude::*, reflect::FromReflect};
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
@@ -24,7 +25,7 @@ impl<K: DamageKind, A: AttributeType> Block<K, A> {
         damage: &Damage<K, A>,
         self_attributes: &Attributes<A>,
         attacker_attributes: &Attributes<A>,
-        rng: &mut StdRng,
+        rng: &mut ChaCha8Rng,
     ) -> (bool, i16) {
         if !self.block_type.iter().any(|k| *k == damage.kind) {
             return (false, 0);
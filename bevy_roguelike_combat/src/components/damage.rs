@@ -4,6 +4,7 @@ use bevy::{
     reflect::{FromReflect, GetTypeRegistration},
 };
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, fmt::Display, hash::Hash, ops::Range};
 
@@ -35,10 +36,10 @@ pub struct Damage<K: DamageKind, A: AttributeType> {
 }
 
 impl<K: DamageKind, A: AttributeType> Damage<K, A> {
-    pub fn compute(&self, attributes: &Attributes<A>, rng: &mut StdRng) -> i32 {
+    pub fn compute(&self, attributes: &Attributes<A>, rng: &mut ChaCha8Rng) -> i32 {
         (self.amount_roll(rng) as f32 * self.amount_multiplier.compute(attributes)) as i32
     }
-    fn amount_roll(&self, rng: &mut StdRng) -> i32 {
+    fn amount_roll(&self, rng: &mut ChaCha8Rng) -> i32 {
         if !self.amount.is_empty() {
             rng.gen_range(self.amount.clone())
         } else {
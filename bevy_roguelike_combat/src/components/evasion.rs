@@ -1,6 +1,7 @@
 use super::{ActionCost, AttributeType, Attributes, Damage, DamageKind, Rate};
 use bevy::{prelude::*, reflect::FromReflect};
 use rand::prelude::*;
+use rand_chacha::ChaCha8Rng;
 use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
@@ -24,7 +25,7 @@ impl<A: AttributeType> Evasion<A> {
         damage: &Damage<K, A>,
         self_attributes: &Attributes<A>,
         attacker_attributes: &Attributes<A>,
-        rng: &mut StdRng,
+        rng: &mut ChaCha8Rng,
     ) -> (bool, i16) {
         let rate_evade = self.chance.compute(self_attributes);
         let rate_hit = damage.hit_chance.compute(attacker_attributes);
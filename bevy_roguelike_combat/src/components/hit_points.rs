@@ -1,5 +1,6 @@
 use super::{AttributeType, Attributes, LinearFormula};
 use bevy::{prelude::*, reflect::FromReflect};
+use serde::{Deserialize, Serialize};
 use std::{fmt::Debug, hash::Hash};
 
 pub const HP_FULL_MIN: i16 = 20;
@@ -10,7 +11,19 @@ pub const HP_REGEN_INCREMENT_MIN: i16 = 16;
 #[reflect(Component)]
 pub struct HitPointsDirty;
 
-#[derive(Default, Debug, Clone, Eq, PartialEq, Hash, Component, Reflect, FromReflect)]
+#[derive(
+    Default,
+    Debug,
+    Clone,
+    Eq,
+    PartialEq,
+    Hash,
+    Component,
+    Reflect,
+    FromReflect,
+    Serialize,
+    Deserialize,
+)]
 #[reflect(Component)]
 pub struct HitPoints<A: AttributeType> {
     is_alive: bool,
@@ -1,12 +1,13 @@
 use super::*;
 use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::Debug;
 
 #[derive(Default, Component, Reflect)]
 #[reflect(Component)]
 pub struct StatsComputedDirty;
 
-#[derive(Component, Debug, Default, Clone, Reflect)]
+#[derive(Component, Debug, Default, Clone, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub struct StatsComputed<K: DamageKind, A: AttributeType> {
     pub attributes: Attributes<A>,
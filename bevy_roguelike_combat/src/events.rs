@@ -1,3 +1,4 @@
+use crate::DamageKind;
 use bevy::prelude::*;
 
 #[derive(Debug, Copy, Clone)]
@@ -35,6 +36,15 @@ pub struct DamageHitPointsEvent {
     pub amount: u16,
 }
 
+/// Attack that got through evasion, block and protection
+#[derive(Debug, Copy, Clone)]
+pub struct HitEvent<K: DamageKind> {
+    pub attacker: Entity,
+    pub defender: Entity,
+    pub kind: K,
+    pub amount: u16,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct DeathEvent {
     pub actor: Entity,
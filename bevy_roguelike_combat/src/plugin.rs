@@ -53,6 +53,7 @@ impl<S: StateData, K: DamageKind, A: AttributeType> Plugin for RoguelikeCombatPl
         .add_event::<AttackEvent>()
         .add_event::<IdleEvent>()
         .add_event::<DeathEvent>()
-        .add_event::<DamageHitPointsEvent>();
+        .add_event::<DamageHitPointsEvent>()
+        .add_event::<HitEvent<K>>();
     }
 }
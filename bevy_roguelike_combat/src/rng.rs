@@ -1,12 +1,21 @@
 use bevy::prelude::Resource;
-use rand::rngs::StdRng;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+use serde::{Deserialize, Serialize};
 use std::ops::{Deref, DerefMut};
 
-#[derive(Resource)]
-pub struct RogueRng(pub StdRng);
+/// Game rng. Its state is serializable so a saved game plays out the same after loading.
+#[derive(Resource, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RogueRng(pub ChaCha8Rng);
+
+impl RogueRng {
+    pub fn seed_from_u64(seed: u64) -> Self {
+        Self(ChaCha8Rng::seed_from_u64(seed))
+    }
+}
 
 impl Deref for RogueRng {
-    type Target = StdRng;
+    type Target = ChaCha8Rng;
 
     fn deref(&self) -> &Self::Target {
         &self.0
@@ -63,6 +63,7 @@ pub fn attack<K: DamageKind, A: AttributeType>(
     mut attack_reader: EventReader<AttackEvent>,
     mut ap_spend_writer: EventWriter<SpendAPEvent>,
     mut damage_writer: EventWriter<DamageHitPointsEvent>,
+    mut hit_writer: EventWriter<HitEvent<K>>,
     mut rng: ResMut<RogueRng>,
 ) {
     for e in attack_reader.iter() {
@@ -180,6 +181,12 @@ pub fn attack<K: DamageKind, A: AttributeType>(
             defender: e.defender,
             amount: true_damage as u16,
         });
+        hit_writer.send(HitEvent {
+            attacker: e.attacker,
+            defender: e.defender,
+            kind: damage.kind.clone(),
+            amount: true_damage as u16,
+        });
 
         log::trace!("attack damage {}", true_damage);
     }
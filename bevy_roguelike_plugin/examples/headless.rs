@@ -0,0 +1,77 @@
+//! Runs a dungeon floor without a window or rendering. The player is controlled by the same AI as monsters
+//! and fights until no actor hostile to it is left standing (or it dies).
+//!
+//! `cargo run --release -p bevy_roguelike_plugin --example headless -- [seed]`
+use bevy::app::AppExit;
+use bevy::prelude::*;
+use bevy::utils::HashMap;
+use bevy_roguelike_plugin::{components::*, headless::*, resources::*};
+
+/// updates before the fight is called a draw (monsters that never meet)
+const UPDATE_LIMIT: u32 = 100_000;
+
+#[derive(Resource, Default)]
+struct UpdateCount(u32);
+
+#[derive(Resource)]
+struct PlayerEntity(Entity);
+
+fn main() {
+    let seed = std::env::args()
+        .nth(1)
+        .and_then(|s| s.parse().ok())
+        .unwrap_or_else(rand::random);
+
+    headless_app(seed)
+        .insert_resource(HeadlessTemplates::load())
+        .init_resource::<UpdateCount>()
+        .add_startup_system(setup)
+        .add_system_to_stage(CoreStage::Last, check_outcome)
+        .run();
+}
+
+fn setup(mut cmd: Commands, templates: Res<HeadlessTemplates>, options: Res<MapOptions>) {
+    let seed = options.seed.unwrap_or_default();
+    let player = templates.spawn_dungeon(&mut cmd, &options, seed);
+    cmd.insert_resource(PlayerEntity(player));
+    println!("seed {}", seed);
+}
+
+fn check_outcome(
+    mut count: ResMut<UpdateCount>,
+    player: Option<Res<PlayerEntity>>,
+    actors: Query<&Faction>,
+    factions: Option<Res<FactionRelations>>,
+    mut exit: EventWriter<AppExit>,
+) {
+    count.0 += 1;
+    let player_faction = player.and_then(|p| actors.get(p.0).ok().copied());
+    let is_enemy_left = match (player_faction, factions.as_ref()) {
+        (Some(player_faction), Some(factions)) => actors
+            .iter()
+            .any(|f| factions.is_hostile(player_faction, *f)),
+        _ => false,
+    };
+    if player_faction.is_some() && is_enemy_left && count.0 < UPDATE_LIMIT {
+        return;
+    }
+    let mut faction_sizes: HashMap<String, usize> = HashMap::default();
+    for faction in actors.iter() {
+        let name = factions.as_ref().map_or("", |f| f.name(*faction));
+        *faction_sizes.entry(name.to_string()).or_default() += 1;
+    }
+    let mut faction_sizes: Vec<_> = faction_sizes.into_iter().collect();
+    faction_sizes.sort();
+    let outcome = if player_faction.is_none() {
+        "player died"
+    } else if is_enemy_left {
+        "draw"
+    } else {
+        "player won"
+    };
+    println!(
+        "{} after {} updates. factions left (name, actors): {:?}",
+        outcome, count.0, faction_sizes
+    );
+    exit.send(AppExit);
+}
@@ -0,0 +1,56 @@
+use bevy::{prelude::*, reflect::FromReflect};
+use serde::{Deserialize, Serialize};
+
+/// Something a monster can do on its turn. Every behaviour the monster has is scored
+/// (base score in the docs, 0 when it does not apply) and multiplied by its weight, the best one is acted on.
+#[derive(Debug, Clone, Copy, PartialEq, Reflect, FromReflect, Serialize, Deserialize)]
+pub enum Behaviour {
+    /// random steps or standing still (0.1)
+    Wander,
+    /// stays within `radius` of the spawn point. standing still (0.15),
+    /// walking back when farther (0.6) and leaving any fight when twice as far (0.9)
+    Guard { radius: i32 },
+    /// goes for the closest visible enemy (0.8) or where one was last seen ([`super::Memory`], 0.4)
+    Chase,
+    /// runs away from visible enemies once hit points drop below the ratio (1.)
+    Flee { hp_below: f32 },
+    /// stays `distance` tiles away from the closest visible enemy, closing in or backing off (0.85)
+    KeepDistance { distance: i32 },
+    /// joins allies within `radius` fighting an enemy it does not see itself (0.7)
+    Pack { radius: i32 },
+    /// goes for visible items and picks them up while the inventory is not full (0.5),
+    /// otherwise to where an item was last seen (0.3)
+    Hoard,
+}
+
+/// Monster controller. Acts on the best scoring of its behaviours every turn.
+#[derive(Debug, Clone, PartialEq, Component, Reflect, FromReflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct Ai {
+    /// behaviours with their weights
+    pub behaviours: Vec<(Behaviour, f32)>,
+    /// spawn point (the spot guarded)
+    pub home: IVec2,
+}
+
+impl Default for Ai {
+    fn default() -> Self {
+        Self::new(&[], IVec2::ZERO)
+    }
+}
+
+impl Ai {
+    /// Chase enemies, hoard items and wander around unless `behaviours` are given
+    pub fn new(behaviours: &[(Behaviour, f32)], home: IVec2) -> Self {
+        let behaviours = if behaviours.is_empty() {
+            vec![
+                (Behaviour::Chase, 1.),
+                (Behaviour::Hoard, 1.),
+                (Behaviour::Wander, 1.),
+            ]
+        } else {
+            behaviours.to_vec()
+        };
+        Self { behaviours, home }
+    }
+}
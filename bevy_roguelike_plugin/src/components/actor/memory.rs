@@ -0,0 +1,81 @@
+use bevy::{prelude::*, reflect::FromReflect};
+use map_generator::BitGrid;
+use serde::{Deserialize, Serialize};
+
+/// Where something was seen and for how many more turns it is remembered
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Reflect, FromReflect, Serialize, Deserialize)]
+pub struct Sighting {
+    pub pt: IVec2,
+    pub turns_left: u32,
+}
+
+/// Last known positions of enemies and items a monster saw.
+/// Sightings fade after [`Memory::turns`] and are dropped as soon as the spot is in sight again
+/// (what is still there is seen anew).
+#[derive(Debug, Clone, PartialEq, Eq, Component, Reflect, FromReflect, Serialize, Deserialize)]
+#[reflect(Component)]
+#[serde(default)]
+pub struct Memory {
+    /// turns a sighting is remembered
+    pub turns: u32,
+    pub enemies: Vec<Sighting>,
+    pub items: Vec<Sighting>,
+}
+
+impl Default for Memory {
+    fn default() -> Self {
+        Self {
+            turns: 20,
+            enemies: vec![],
+            items: vec![],
+        }
+    }
+}
+
+impl Memory {
+    /// Replaces sightings of `visible` tiles with what is seen there now
+    pub fn observe(
+        &mut self,
+        visible: &BitGrid,
+        enemies: impl IntoIterator<Item = IVec2>,
+        items: impl IntoIterator<Item = IVec2>,
+    ) {
+        let turns_left = self.turns;
+        let sighting = |pt| Sighting { pt, turns_left };
+        self.enemies.retain(|s| !visible.contains(s.pt));
+        self.enemies.extend(enemies.into_iter().map(sighting));
+        self.items.retain(|s| !visible.contains(s.pt));
+        self.items.extend(items.into_iter().map(sighting));
+    }
+
+    /// A turn passes, old sightings are forgotten
+    pub fn tick(&mut self) {
+        for s in self.enemies.iter_mut().chain(self.items.iter_mut()) {
+            s.turns_left = s.turns_left.saturating_sub(1);
+        }
+        self.enemies.retain(|s| s.turns_left > 0);
+        self.items.retain(|s| s.turns_left > 0);
+    }
+
+    /// where an enemy was seen most recently (the first one seen if several)
+    pub fn last_enemy(&self) -> Option<IVec2> {
+        Self::freshest(&self.enemies)
+    }
+
+    /// where an item was seen most recently (the first one seen if several)
+    pub fn last_item(&self) -> Option<IVec2> {
+        Self::freshest(&self.items)
+    }
+
+    fn freshest(sightings: &[Sighting]) -> Option<IVec2> {
+        sightings
+            .iter()
+            .rev()
+            .max_by_key(|s| s.turns_left)
+            .map(|s| s.pt)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.enemies.is_empty() && self.items.is_empty()
+    }
+}
@@ -9,6 +9,12 @@ use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use strum_macros::EnumIter;
 
+pub use ai::{Ai, Behaviour};
+pub use memory::{Memory, Sighting};
+
+mod ai;
+mod memory;
+
 #[derive(
     Debug,
     Default,
@@ -64,28 +70,27 @@ fn from_display<I: ItemType>(display: &EquipmentDisplay<I>) -> Equipment<I> {
 #[derive(Bundle)]
 pub struct Actor {
     name: Name,
-    team: Team,
+    faction: Faction,
     state: TurnState,
     combat: Combat<RogueDamageKind, RogueAttributeType>,
     fov: FieldOfView,
     position: Vector2D,
-    render_info: RenderInfo,
     equipment_display: EquipmentDisplay<RogueItemType>,
     equipment: Equipment<RogueItemType>,
     inventory: Inventory,
 }
 impl Actor {
     /// Creates a new [`Actor`] using specified [`ActorTemplate`].
+    /// Textures are not included (see [`crate::resources::ActorRenderInfo::load`]).
     pub fn new(
-        asset_server: AssetServer,
         template: &ActorTemplate,
         combat_settings: &CombatSettings,
-        team: u32,
+        faction: Faction,
         position: IVec2,
     ) -> Self {
         Self {
             name: Name::new(template.render.name.clone()),
-            team: Team::new(team),
+            faction,
             state: TurnState::default(),
             combat: Combat::new(
                 &template.attributes,
@@ -102,48 +107,36 @@ impl Actor {
             equipment_display: template.equipment_display.clone(),
             equipment: from_display(&template.equipment_display),
             position: Vector2D::from(position),
-            render_info: RenderInfo {
-                texture: asset_server.load(template.render.texture_path.as_str()),
-                cosmetic_textures: template
-                    .render
-                    .texture_path_cosmetics
-                    .iter()
-                    .map(|p| asset_server.load(p.as_str()))
-                    .collect(),
-                z: 2.,
-            },
         }
     }
 }
 
-#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
-#[reflect(Component)]
-pub enum TurnState {
-    #[default]
-    Collect,
-    Act,
-    End,
-}
-
 #[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
 #[reflect(Component)]
 pub struct MovingPlayer;
 
-#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
-#[reflect(Component)]
-pub struct MovingRandom;
-
-#[derive(Debug, Default, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
-#[reflect(Component)]
-pub struct MovingFovRandom;
-
-#[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
+/// Faction the actor belongs to. Relations between factions are kept in [`crate::resources::FactionRelations`].
+#[derive(
+    Default,
+    Debug,
+    Copy,
+    Clone,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Component,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
 #[reflect(Component)]
-pub struct Team {
+pub struct Faction {
     id: u32,
 }
 
-impl Team {
+impl Faction {
     pub fn new(id: u32) -> Self {
         Self { id }
     }
@@ -152,6 +145,18 @@ impl Team {
     }
 }
 
+/// How actors of one faction treat actors of another
+#[derive(Default, Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum Relation {
+    /// attack and chase each other
+    #[default]
+    Hostile,
+    /// leave each other alone. attacking a neutral actor may turn its faction hostile
+    Neutral,
+    /// never attack each other and swap places instead of blocking each other
+    Allied,
+}
+
 // TODO: fix lousy name
 #[derive(Default, Debug, Clone, Eq, PartialEq, Component, Reflect)]
 #[reflect(Component)]
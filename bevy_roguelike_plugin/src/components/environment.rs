@@ -1,10 +1,47 @@
 use bevy::prelude::*;
+use map_generator::Tile;
 
 #[derive(Default, Debug, Copy, Clone, Ord, PartialOrd, Eq, PartialEq, Hash, Component, Reflect)]
 #[reflect(Component)]
 pub struct MapTile {
     pub is_passable: bool,
-    // TODO: include cost of moving into a tile
-
+    pub blocks_sight: bool,
+    /// cost of moving into a tile relative to a floor tile
+    pub movement_cost: i32,
+    pub damage_on_enter: u16,
     // TODO: should also describe the kind of tile (what sides are open)
 }
+
+impl From<Tile> for MapTile {
+    fn from(tile: Tile) -> Self {
+        Self {
+            is_passable: tile.is_passable(),
+            blocks_sight: tile.blocks_sight(),
+            movement_cost: tile.movement_cost().unwrap_or_default(),
+            damage_on_enter: tile.damage_on_enter(),
+        }
+    }
+}
+
+/// Tile entities of a dungeon floor, kept on its map root entity so tiles are found by position
+#[derive(Debug, Clone, Component)]
+pub struct MapTiles {
+    size: IVec2,
+    tiles: Vec<Entity>,
+}
+
+impl MapTiles {
+    /// `tiles` are in [`map_generator::Map::enumerate`] order
+    pub fn new(size: IVec2, tiles: Vec<Entity>) -> Self {
+        Self { size, tiles }
+    }
+
+    pub fn get(&self, pt: IVec2) -> Option<Entity> {
+        if pt.cmplt(IVec2::ZERO).any() || pt.cmpge(self.size).any() {
+            return None;
+        }
+        self.tiles
+            .get((pt.y * self.size.x + pt.x) as usize)
+            .copied()
+    }
+}
@@ -1,6 +1,8 @@
 use super::RogueAttributeType;
-use bevy::{prelude::*, utils::HashSet};
+use bevy::prelude::*;
 use bevy_roguelike_combat::Attributes;
+use map_generator::BitGrid;
+use serde::{Deserialize, Serialize};
 
 // #[derive(Default, Component, Reflect)]
 // #[reflect(Component)]
@@ -10,12 +12,14 @@ use bevy_roguelike_combat::Attributes;
 #[reflect(Component)]
 pub struct FieldOfViewDirty;
 
-#[derive(Default, Debug, Clone, Eq, PartialEq, Component, Reflect)]
+#[derive(Default, Debug, Clone, Eq, PartialEq, Component, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub struct FieldOfView {
     pub radius: i32,
-    pub tiles_visible: HashSet<IVec2>,
-    pub tiles_revealed: HashSet<IVec2>,
+    #[reflect(ignore)]
+    pub tiles_visible: BitGrid,
+    #[reflect(ignore)]
+    pub tiles_revealed: BitGrid,
     // TODO: refactor as a separate dirty component (do not clash with FieldOfViewDirty use.. better name?)
     pub is_dirty: bool,
 }
@@ -30,8 +34,8 @@ impl FieldOfView {
                 + (atr.get(&RogueAttributeType::Perception) as f32 / 3.
                     + atr.get(&RogueAttributeType::Inteligence) as f32 / 10.)
                     as i32,
-            tiles_visible: HashSet::default(),
-            tiles_revealed: HashSet::default(),
+            tiles_visible: BitGrid::default(),
+            tiles_revealed: BitGrid::default(),
             is_dirty: true,
         }
     }
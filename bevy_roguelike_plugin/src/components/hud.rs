@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+/// Ui text showing the run seed and the dungeon depth
+#[derive(Default, Debug, Clone, Eq, PartialEq, Component, Reflect)]
+#[reflect(Component)]
+pub struct HudRunInfo;
@@ -7,9 +7,10 @@ use bevy::prelude::*;
 use bevy_inventory_ui::UiRenderInfo;
 use rand::prelude::*;
 
+/// Inserts item type and stats (mutated by quality). Textures are inserted separately
+/// with [`insert_item_render`] so items can be spawned without an `AssetServer`.
 pub fn spawn_item(
     ecmd: &mut EntityCommands,
-    asset_server: AssetServer,
     template: &ItemTemplate,
     quality: &Quality,
     rng: &mut StdRng,
@@ -19,7 +20,7 @@ pub fn spawn_item(
     match template {
         ItemTemplate::Weapon(Weapon { render, damage }) => {
             ecmd.insert(RogueItemType::MainHand);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             ecmd.insert(damage.mutate(quality, rng));
         }
         ItemTemplate::Shield(Shield {
@@ -28,7 +29,7 @@ pub fn spawn_item(
             block,
         }) => {
             ecmd.insert(RogueItemType::OffHand);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             ecmd.insert(protection.mutate(quality, rng))
                 .insert(block.mutate(quality, rng));
         }
@@ -38,7 +39,7 @@ pub fn spawn_item(
             enchantment,
         }) => {
             ecmd.insert(RogueItemType::Head);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             insert_defense(ecmd, defense, quality, rng);
             insert_enchantment(ecmd, enchantment, quality, rng);
         }
@@ -48,7 +49,7 @@ pub fn spawn_item(
             enchantment,
         }) => {
             ecmd.insert(RogueItemType::Body);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             insert_defense(ecmd, defense, quality, rng);
             insert_enchantment(ecmd, enchantment, quality, rng);
         }
@@ -58,7 +59,7 @@ pub fn spawn_item(
             enchantment,
         }) => {
             ecmd.insert(RogueItemType::Feet);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             insert_defense(ecmd, defense, quality, rng);
             insert_enchantment(ecmd, enchantment, quality, rng);
         }
@@ -68,7 +69,7 @@ pub fn spawn_item(
             enchantment,
         }) => {
             ecmd.insert(RogueItemType::Neck);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             insert_defense(ecmd, defense, quality, rng);
             insert_enchantment(ecmd, enchantment, quality, rng);
         }
@@ -78,11 +79,14 @@ pub fn spawn_item(
             enchantment,
         }) => {
             ecmd.insert(RogueItemType::Finger);
-            insert_render(ecmd, asset_server, render);
+            ecmd.insert(Name::new(render.name.clone()));
             insert_defense(ecmd, defense, quality, rng);
             insert_enchantment(ecmd, enchantment, quality, rng);
         }
     }
+    if let Some(light) = template.render().light {
+        ecmd.insert(light);
+    }
 }
 
 fn insert_defense(
@@ -109,10 +113,15 @@ fn insert_enchantment(
     }
 }
 
-fn insert_render(ecmd: &mut EntityCommands, asset_server: AssetServer, render: &ItemRenderInfo) {
+/// Inserts textures of the item placed on the map, in the inventory and equiped
+pub fn insert_item_render(
+    ecmd: &mut EntityCommands,
+    asset_server: &AssetServer,
+    template: &ItemTemplate,
+) {
+    let render = template.render();
     let texture = asset_server.load(render.texture_path.as_str());
     ecmd.insert((
-        Name::new(render.name.clone()),
         UiRenderInfo {
             image: texture.clone().into(),
         },
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy_inventory::ItemType;
-pub use from_template::spawn_item;
+pub use from_template::{insert_item_render, spawn_item};
 pub use quality::MutableQuality;
 pub use quality::Quality;
 use serde::Deserialize;
@@ -2,12 +2,14 @@ use bevy::prelude::*;
 use bevy::utils::HashMap;
 use bevy_roguelike_combat::*;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 use std::fmt::Display;
 use std::ops::Range;
+use strum::IntoEnumIterator;
 
 use crate::components::{RogueAttributeType, RogueDamageKind};
 
-#[derive(Default, Component, Clone, Reflect)]
+#[derive(Default, Component, Clone, Reflect, Serialize, Deserialize)]
 #[reflect(Component)]
 pub enum Quality {
     Broken,
@@ -29,19 +31,19 @@ impl Display for Quality {
     }
 }
 impl Quality {
-    pub fn roll(rng: &mut StdRng) -> Self {
+    /// `bonus` shifts the roll towards better quality (deeper dungeon floors), a negative one towards worse
+    pub fn roll(rng: &mut StdRng, bonus: i32) -> Self {
         // Broken 5 %
         // Damaged 20 %
         // Normal 50%
         // Masterwork 20 %
         // Artifact 5 %
-        match rng.gen_range(0..100) {
-            0..=5 => Self::Broken,
+        match (rng.gen_range(0..100) + bonus).clamp(0, 100) {
+            ..=5 => Self::Broken,
             6..=25 => Self::Damaged,
             26..=75 => Self::Normal,
             76..=95 => Self::Masterwork,
-            96..=100 => Self::Artifact,
-            _ => Self::Normal,
+            96.. => Self::Artifact,
         }
     }
     /// broken (20% .. 60%),
@@ -291,8 +293,12 @@ impl MutableQuality for Block<RogueDamageKind, RogueAttributeType> {
 impl MutableQuality for Attributes<RogueAttributeType> {
     fn mutate_extended(&self, is_direct: bool, quality: &Quality, rng: &mut StdRng) -> Self {
         Self {
-            list: HashMap::from_iter(self.clone().list.into_iter().filter_map(|(t, v)| {
-                let attribute = (t, v.mutate_extended(is_direct, quality, rng));
+            // NOTE: attributes are mutated in declaration order (not hash map order) to follow the run seed
+            list: HashMap::from_iter(RogueAttributeType::iter().filter_map(|t| {
+                let attribute = (
+                    t,
+                    self.list.get(&t)?.mutate_extended(is_direct, quality, rng),
+                );
                 if attribute.1 > 0 {
                     Some(attribute)
                 } else {
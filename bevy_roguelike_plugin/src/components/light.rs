@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use map_generator::Light;
+use serde::{Deserialize, Serialize};
+
+/// Light given off by the entity on its tile. Equiped items light the tile of their owner.
+#[derive(Default, Debug, Clone, Copy, PartialEq, Component, Reflect, Serialize, Deserialize)]
+#[reflect(Component)]
+pub struct LightSource {
+    /// farthest lit tile
+    pub radius: i32,
+    /// light level at the source (up to 1.), fading out towards the radius
+    pub intensity: f32,
+}
+
+impl From<LightSource> for Light {
+    fn from(light: LightSource) -> Self {
+        Light {
+            radius: light.radius,
+            intensity: light.intensity,
+        }
+    }
+}
+
+/// Light going out after a few turns (fire hits)
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Component, Reflect)]
+#[reflect(Component)]
+pub struct LightFlash {
+    pub turns_left: u32,
+}
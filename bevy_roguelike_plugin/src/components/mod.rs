@@ -1,21 +1,29 @@
 pub use actor::Actor;
+pub use actor::Ai;
+pub use actor::Behaviour;
+pub use actor::Faction;
 pub use actor::HudHealthBar;
-pub use actor::MovingFovRandom;
+pub use actor::Memory;
 pub use actor::MovingPlayer;
-pub use actor::MovingRandom;
+pub use actor::Relation;
 pub use actor::RogueAttributeType;
-pub use actor::Team;
-pub use actor::TurnState;
+pub use actor::Sighting;
+pub use bevy_roguelike_turns::TurnState;
 pub use damage::RogueDamageKind;
 pub use environment::MapTile;
+pub use environment::MapTiles;
 pub use fov::FieldOfView;
 pub use fov::FieldOfViewDirty;
+pub use hud::HudRunInfo;
+pub use item::insert_item_render;
 pub use item::spawn_item;
 pub use item::EquipedRenderedItem;
 pub use item::EquipedRendition;
 pub use item::ItemEquipedOwned;
 pub use item::Quality;
 pub use item::RogueItemType;
+pub use light::LightFlash;
+pub use light::LightSource;
 pub use render_info::RenderInfo;
 pub use render_info::RenderInfoEquiped;
 pub use vector2d::Vector2D;
@@ -24,6 +32,8 @@ mod actor;
 mod damage;
 mod environment;
 mod fov;
+mod hud;
 mod item;
+mod light;
 mod render_info;
 mod vector2d;
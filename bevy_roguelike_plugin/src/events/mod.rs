@@ -1,6 +1,8 @@
-use crate::components::Team;
+use crate::components::Faction;
 use bevy::prelude::*;
 
+pub use bevy_roguelike_turns::TurnStartEvent;
+
 // TODO: turn into Act component with is_dirty or is_used
 #[derive(Debug, Copy, Clone)]
 pub struct ActEvent {
@@ -11,7 +13,7 @@ pub struct ActEvent {
 #[derive(Debug, Copy, Clone)]
 pub struct MoveEvent {
     pub actor: Entity,
-    pub team: Team,
+    pub faction: Faction,
     pub from: IVec2,
     pub to: IVec2,
 }
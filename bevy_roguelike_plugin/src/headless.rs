@@ -0,0 +1,116 @@
+//! Game without a window or asset server: templates are read straight from the asset files.
+//! Used by the `headless` example and the integration tests.
+use crate::{components::*, resources::*, systems::dungeon::*, RoguelikeSimulationPlugin};
+use bevy::prelude::*;
+use serde::de::DeserializeOwned;
+use std::path::{Path, PathBuf};
+
+/// asset folder of the workspace
+pub const ASSETS: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../assets");
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum SimState {
+    Running,
+}
+
+/// App running [`RoguelikeSimulationPlugin`] on top of `MinimalPlugins`. Maps are generated from `seed`.
+pub fn headless_app(seed: u64) -> App {
+    let mut app = App::new();
+    app.add_state_to_stage(CoreStage::First, SimState::Running)
+        .add_state_to_stage(CoreStage::PreUpdate, SimState::Running)
+        .add_state_to_stage(CoreStage::Update, SimState::Running)
+        .add_state_to_stage(CoreStage::PostUpdate, SimState::Running)
+        .add_state_to_stage(CoreStage::Last, SimState::Running)
+        .add_plugins(MinimalPlugins)
+        .add_plugin(RoguelikeSimulationPlugin {
+            state_running: SimState::Running,
+        })
+        .insert_resource(MapOptions {
+            seed: Some(seed),
+            ..Default::default()
+        });
+    app
+}
+
+/// Owned templates read straight from the asset files (no asset server)
+#[derive(Resource)]
+pub struct HeadlessTemplates {
+    pub map_gen_presets: Vec<MapGenPreset>,
+    pub prefabs: Vec<PrefabAsset>,
+    pub item_templates: Vec<ItemTemplate>,
+    pub actor_templates: Vec<ActorTemplate>,
+    pub combat_settings: CombatSettings,
+    pub factions: Factions,
+    pub player_template: ActorTemplate,
+}
+
+impl HeadlessTemplates {
+    /// reads every template under [`ASSETS`]. the player is a human.
+    pub fn load() -> Self {
+        Self {
+            map_gen_presets: load_all(ASSETS, ".mapgen.ron"),
+            prefabs: load_all(ASSETS, ".prefab.ron"),
+            item_templates: load_all(ASSETS, ".item.ron"),
+            actor_templates: load_all(ASSETS, ".actor.ron"),
+            combat_settings: load_all(ASSETS, ".combat.ron")
+                .into_iter()
+                .next()
+                .expect("combat settings asset should exist"),
+            factions: load_all(ASSETS, ".factions.ron")
+                .into_iter()
+                .next()
+                .expect("factions asset should exist"),
+            player_template: load(Path::new(ASSETS).join("actors/human.actor.ron")),
+        }
+    }
+
+    pub fn floor_templates(&self) -> FloorTemplates<'_> {
+        FloorTemplates {
+            map_gen_presets: self.map_gen_presets.iter().collect(),
+            prefabs: self.prefabs.iter().map(|it| it.0.clone()).collect(),
+            map_themes: vec![],
+            item_templates: self.item_templates.iter().collect(),
+            actor_templates: self.actor_templates.iter().collect(),
+            combat_settings: &self.combat_settings,
+            factions: &self.factions,
+        }
+    }
+
+    /// spawns the first floor (see [`spawn_dungeon`]) and a player controlled by the same AI as monsters
+    pub fn spawn_dungeon(&self, cmd: &mut Commands, options: &MapOptions, seed: u64) -> Entity {
+        let (player, start) = spawn_dungeon(
+            cmd,
+            &self.floor_templates(),
+            &self.player_template,
+            None,
+            options,
+            seed,
+        );
+        cmd.entity(player)
+            .insert((Ai::new(&[], start), Memory::default()));
+        player
+    }
+}
+
+/// every file under `dir` ending with `extension`, sorted by path (like assets sorted by handle)
+pub fn load_all<T: DeserializeOwned>(dir: impl AsRef<Path>, extension: &str) -> Vec<T> {
+    let mut paths = vec![];
+    let mut dirs = vec![dir.as_ref().to_path_buf()];
+    while let Some(dir) = dirs.pop() {
+        for entry in std::fs::read_dir(&dir).expect("asset dir should be readable") {
+            let path = entry.expect("asset dir entry").path();
+            if path.is_dir() {
+                dirs.push(path);
+            } else if path.to_string_lossy().ends_with(extension) {
+                paths.push(path);
+            }
+        }
+    }
+    paths.sort();
+    paths.into_iter().map(load).collect()
+}
+
+pub fn load<T: DeserializeOwned>(path: PathBuf) -> T {
+    let text = std::fs::read_to_string(&path).expect("asset should be readable");
+    ron::from_str(&text).unwrap_or_else(|e| panic!("invalid asset {}: {}", path.display(), e))
+}
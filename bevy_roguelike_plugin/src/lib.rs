@@ -1,5 +1,6 @@
 pub mod components;
 pub mod events;
+pub mod headless;
 pub mod resources;
 pub mod systems;
 
@@ -8,25 +9,30 @@ use crate::events::*;
 use bevy::ecs::schedule::StateData;
 use bevy::log;
 use bevy::prelude::*;
-use bevy::utils::HashSet;
 use bevy_common_assets::ron::RonAssetPlugin;
+use bevy_inventory::{ItemDropEvent, ItemPickUpEvent};
 use bevy_inventory_ui::InventoryUiAssets;
 use bevy_inventory_ui::InventoryUiPlugin;
 use bevy_roguelike_combat::*;
+use bevy_roguelike_turns::RoguelikeTurnsPlugin;
 use bevy_tweening::TweeningPlugin;
-use map_generator::*;
 use rand::prelude::*;
 use resources::*;
 use std::marker::PhantomData;
 use std::ops::Range;
 use systems::action::*;
 use systems::actor_stats::*;
+use systems::ai::*;
 use systems::camera::*;
+use systems::dungeon::*;
+use systems::faction::*;
 use systems::fov::*;
 use systems::input::*;
 use systems::inventory::*;
+use systems::light::*;
 use systems::map::*;
 use systems::render::*;
+use systems::save::*;
 use systems::turns::*;
 
 // TODO: review all `as T` casting
@@ -65,109 +71,157 @@ impl<T: StateNext> StateSetNext for State<T> {
 #[derive(Resource, Default)]
 pub struct AssetsLoading(pub Vec<HandleUntyped>);
 
-#[derive(Resource, Debug)]
-pub struct MapEntities {
-    map_id: Entity,
-    enemies_id: Entity,
-    items_id: Entity,
-}
-
 // TODO: instead of after / before  use labels: https://bevy-cheatbook.github.io/programming/system-order.html#labels
 
-impl<T: StateNext> Plugin for RoguelikePlugin<T> {
+/// Game logic without rendering, input or asset loading: turns, combat, movement,
+/// field of view, inventory and AI. Runs with `MinimalPlugins` (see the `headless` example).
+/// The dungeon itself is spawned with [`spawn_dungeon`].
+pub struct RoguelikeSimulationPlugin<T> {
+    pub state_running: T,
+}
+
+impl<T: StateData> Plugin for RoguelikeSimulationPlugin<T> {
     fn build(&self, app: &mut App) {
-        app.add_plugin(TweeningPlugin {})
-            .add_plugin(InventoryUiPlugin::<_, RogueItemType, InventoryAssets> {
+        app.add_plugin(
+            RoguelikeCombatPlugin::<_, RogueDamageKind, RogueAttributeType> {
                 state_running: self.state_running.clone(),
                 phantom_1: PhantomData {},
                 phantom_2: PhantomData {},
-            })
-            .add_plugin(
-                RoguelikeCombatPlugin::<_, RogueDamageKind, RogueAttributeType> {
-                    state_running: self.state_running.clone(),
-                    phantom_1: PhantomData {},
-                    phantom_2: PhantomData {},
-                },
-            )
-            .add_plugin(RonAssetPlugin::<ItemTemplate>::new(&["item.ron"]))
-            .add_plugin(RonAssetPlugin::<ActorTemplate>::new(&["actor.ron"]))
-            .add_plugin(RonAssetPlugin::<MapTheme>::new(&["maptheme.ron"]))
-            .add_plugin(RonAssetPlugin::<InventoryTheme>::new(&[
-                "inventorytheme.ron",
-            ]))
-            .add_plugin(RonAssetPlugin::<CombatSettings>::new(&["combat.ron"]))
-            .insert_resource(AssetsLoading::default())
-            .add_startup_system(Self::rogue_setup)
-            .add_startup_system(setup_camera)
-            .add_system_set(
-                SystemSet::on_update(self.state_asset_load.clone())
-                    .with_system(Self::check_assets_ready),
-            )
-            .add_system_set(
-                SystemSet::on_enter(self.state_construct.clone()).with_system(Self::create_map),
-            )
-            .add_system_set_to_stage(
-                CoreStage::First,
-                SystemSet::on_update(self.state_running.clone())
-                    .with_system(apply_position_to_transform)
-                    .with_system(camera_set_focus_player)
-                    .with_system(field_of_view_recompute),
-            )
-            .add_system_set_to_stage(
-                CoreStage::PreUpdate,
-                SystemSet::on_update(self.state_running.clone())
-                    .with_system(gather_action_points)
-                    .with_system(turn_end_now_gather)
-                    .with_system(stats_recompute::<RogueItemType>)
-                    .with_system(attributes_update_field_of_view)
-                    .with_system(field_of_view_set_visibility)
-                    .with_system(actors_fill_text_info)
-                    .with_system(item_fill_text_info::<RogueItemType>)
-                    .with_system(camera_focus_smooth)
-                    .with_system(equip_owned_add::<RogueItemType>)
-                    .with_system(equip_owned_remove::<RogueItemType>)
-                    .with_system(toggle_inventory_open_event_send::<RogueItemType>),
-            )
-            .add_system_set_to_stage(
-                CoreStage::Update,
-                SystemSet::on_update(self.state_running.clone())
-                    .with_system(input_player::<RogueItemType>)
-                    .with_system(input_fov_rand)
-                    .with_system(render_body)
-                    .with_system(render_equiped_item::<RogueItemType>)
-                    .with_system(unrender_unequiped_items)
-                    .with_system(render_hud_health_bar)
-                    .with_system(act)
-                    .with_system(action_completed)
-                    .with_system(try_move.after(act)),
-            )
-            .add_system_set_to_stage(
-                CoreStage::PostUpdate,
-                SystemSet::on_update(self.state_running.clone())
-                    .with_system(pick_up_items::<RogueItemType>)
-                    .with_system(drop_item::<RogueItemType>)
-                    .with_system(death_read::<RogueItemType>),
-            )
-            .add_system_set(
-                SystemSet::on_exit(self.state_running.clone()).with_system(Self::cleanup_map),
-            )
-            .register_type::<Vector2D>()
-            .register_type::<RenderInfo>()
-            .register_type::<RenderInfoEquiped>()
-            .register_type::<MapTile>()
-            .register_type::<TurnState>()
-            .register_type::<Team>()
-            .register_type::<MovingPlayer>()
-            .register_type::<MovingRandom>()
-            .register_type::<MovingFovRandom>()
-            .register_type::<FieldOfView>()
-            .register_type::<FieldOfViewDirty>()
-            .register_type::<Quality>()
-            .register_type::<HashSet<IVec2>>()
-            .register_type::<Range<i32>>()
-            .add_event::<MoveEvent>()
-            .add_event::<ActEvent>()
-            .add_event::<CameraFocusEvent>();
+            },
+        )
+        .add_plugin(RoguelikeTurnsPlugin::<_, RogueAttributeType> {
+            state_running: self.state_running.clone(),
+            phantom: PhantomData {},
+        })
+        .add_system_set_to_stage(
+            CoreStage::First,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(light_map_recompute.before(field_of_view_recompute))
+                .with_system(field_of_view_recompute),
+        )
+        .add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(stats_recompute::<RogueItemType>)
+                .with_system(attributes_update_field_of_view)
+                .with_system(equip_owned_add::<RogueItemType>)
+                .with_system(equip_owned_remove::<RogueItemType>),
+        )
+        .add_system_set_to_stage(
+            CoreStage::Update,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(memory_tick.before(input_ai))
+                .with_system(input_ai)
+                .with_system(act)
+                .with_system(turn_start_clear_fov_cache)
+                .with_system(try_move.after(act)),
+        )
+        .add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(pick_up_items::<RogueItemType>)
+                .with_system(drop_item::<RogueItemType>)
+                .with_system(death_read::<RogueItemType>)
+                .with_system(faction_provoke)
+                .with_system(light_fire_hits)
+                .with_system(light_flash_fade),
+        )
+        .init_resource::<FovCache>()
+        .init_resource::<RogueLightMap>()
+        .register_type::<Vector2D>()
+        .register_type::<MapTile>()
+        .register_type::<Faction>()
+        .register_type::<MovingPlayer>()
+        .register_type::<Ai>()
+        .register_type::<Memory>()
+        .register_type::<Sighting>()
+        .register_type::<FieldOfView>()
+        .register_type::<FieldOfViewDirty>()
+        .register_type::<Quality>()
+        .register_type::<LightSource>()
+        .register_type::<LightFlash>()
+        .register_type::<Range<i32>>()
+        .add_event::<MoveEvent>()
+        .add_event::<ActEvent>()
+        .add_event::<ItemPickUpEvent>()
+        .add_event::<ItemDropEvent>();
+
+        #[cfg(feature = "debug")]
+        app.add_system_set_to_stage(
+            CoreStage::PostUpdate,
+            SystemSet::on_update(self.state_running.clone()).with_system(memory_debug_log),
+        );
+
+        log::info!("Loaded Roguelike Simulation Plugin");
+    }
+}
+
+impl<T: StateNext> Plugin for RoguelikePlugin<T> {
+    fn build(&self, app: &mut App) {
+        app.add_plugin(RoguelikeSimulationPlugin {
+            state_running: self.state_running.clone(),
+        })
+        .add_plugin(TweeningPlugin {})
+        .add_plugin(InventoryUiPlugin::<_, RogueItemType, InventoryAssets> {
+            state_running: self.state_running.clone(),
+            phantom_1: PhantomData {},
+            phantom_2: PhantomData {},
+        })
+        .add_plugin(RonAssetPlugin::<ItemTemplate>::new(&["item.ron"]))
+        .add_plugin(RonAssetPlugin::<ActorTemplate>::new(&["actor.ron"]))
+        .add_plugin(RonAssetPlugin::<MapTheme>::new(&["maptheme.ron"]))
+        .add_plugin(RonAssetPlugin::<MapGenPreset>::new(&["mapgen.ron"]))
+        .add_plugin(RonAssetPlugin::<PrefabAsset>::new(&["prefab.ron"]))
+        .add_plugin(RonAssetPlugin::<InventoryTheme>::new(&[
+            "inventorytheme.ron",
+        ]))
+        .add_plugin(RonAssetPlugin::<CombatSettings>::new(&["combat.ron"]))
+        .add_plugin(RonAssetPlugin::<Factions>::new(&["factions.ron"]))
+        .insert_resource(AssetsLoading::default())
+        .add_startup_system(Self::rogue_setup)
+        .add_startup_system(setup_camera)
+        .add_system_set(
+            SystemSet::on_update(self.state_asset_load.clone())
+                .with_system(Self::check_assets_ready),
+        )
+        .add_system_set(
+            SystemSet::on_enter(self.state_construct.clone()).with_system(Self::create_map),
+        )
+        .add_system_set_to_stage(
+            CoreStage::First,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(apply_position_to_transform)
+                .with_system(camera_set_focus_player),
+        )
+        .add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(field_of_view_set_visibility)
+                .with_system(actors_fill_text_info)
+                .with_system(item_fill_text_info::<RogueItemType>)
+                .with_system(camera_focus_smooth)
+                .with_system(toggle_inventory_open_event_send::<RogueItemType>),
+        )
+        .add_system_set_to_stage(
+            CoreStage::Update,
+            SystemSet::on_update(self.state_running.clone())
+                .with_system(input_player::<RogueItemType>)
+                .with_system(render_body)
+                .with_system(render_equiped_item::<RogueItemType>)
+                .with_system(unrender_unequiped_items)
+                .with_system(render_hud_health_bar)
+                .with_system(render_hud_run_info)
+                .with_system(use_stairs)
+                .with_system(save_game)
+                .with_system(load_game),
+        )
+        .add_system_set(
+            SystemSet::on_exit(self.state_running.clone()).with_system(Self::cleanup_map),
+        )
+        .register_type::<RenderInfo>()
+        .register_type::<RenderInfoEquiped>()
+        .register_type::<HudRunInfo>()
+        .add_event::<CameraFocusEvent>();
 
         log::info!("Loaded Roguelike Plugin");
     }
@@ -199,12 +253,29 @@ impl<T: StateNext> RoguelikePlugin<T> {
         }
     }
 
-    fn cleanup_map(map_id: Res<MapEntities>, mut cmd: Commands) {
-        cmd.entity(map_id.map_id).despawn_recursive();
-        cmd.entity(map_id.enemies_id).despawn_recursive();
-        // TODO: leave player items
-        cmd.entity(map_id.items_id).despawn_recursive();
+    fn cleanup_map(
+        map_id: Res<MapEntities>,
+        dungeon: Res<Dungeon>,
+        huds: Query<Entity, With<HudRunInfo>>,
+        mut cmd: Commands,
+    ) {
+        for entities in dungeon
+            .floors
+            .values()
+            .map(|f| &f.entities)
+            .chain([&*map_id])
+        {
+            cmd.entity(entities.map_id).despawn_recursive();
+            cmd.entity(entities.enemies_id).despawn_recursive();
+            // TODO: leave player items
+            cmd.entity(entities.items_id).despawn_recursive();
+        }
+        for hud in huds.iter() {
+            cmd.entity(hud).despawn_recursive();
+        }
         cmd.remove_resource::<MapEntities>();
+        cmd.remove_resource::<Dungeon>();
+        cmd.remove_resource::<RunSeed>();
     }
 
     fn rogue_setup(
@@ -228,17 +299,12 @@ impl<T: StateNext> RoguelikePlugin<T> {
         state.set_next();
     }
 
-    #[allow(clippy::too_many_arguments)]
     pub fn create_map(
         mut cmd: Commands,
         mut state: ResMut<State<T>>,
         map_options: Option<Res<MapOptions>>,
-        asset_server: Res<AssetServer>,
-        map_themes: Res<Assets<MapTheme>>,
-        item_templates: Res<Assets<ItemTemplate>>,
+        floor_assets: FloorAssets,
         inventory_themes: Res<Assets<InventoryTheme>>,
-        combat_settings: Res<Assets<CombatSettings>>,
-        actor_templates: Res<Assets<ActorTemplate>>,
         mut cameras: Query<&mut Transform, With<Camera2d>>,
     ) {
         let options = match map_options {
@@ -246,29 +312,42 @@ impl<T: StateNext> RoguelikePlugin<T> {
             Some(o) => o.clone(),
         };
 
-        // max u64: 18_446_744_073_709_551_615
-        // let mut rng = StdRng::seed_from_u64(54155745465);
-        let trng = thread_rng();
-        let mut rng = StdRng::from_rng(trng).expect("Could not construct StdRng using ThreadRng");
-
-        let map_generator = RandomMapGenerator {};
-        let map = map_generator.gen(&mut rng, options.map_size);
-        let info = MapInfo::from_map(&map, &mut rng);
+        let seed = options.seed.unwrap_or_else(|| thread_rng().gen());
+        bevy::log::info!("run seed: {}", seed);
 
-        #[cfg(feature = "debug")]
-        log::trace!("{}", map.to_colorized_string());
-        #[cfg(feature = "debug")]
-        log::info!("{}", info.to_colorized_string());
+        let asset_server = floor_assets.asset_server.clone();
+        let templates = floor_assets.templates(&options);
+        bevy::log::info!("map gen preset count: {}", templates.map_gen_presets.len());
+        let player_template = if let Some(template) = floor_assets
+            .actor_templates
+            .get(&asset_server.load("actors/human.actor.ron"))
+        {
+            template
+        } else {
+            bevy::log::error!("human actor template not found");
+            return;
+        };
+        let (player, player_start) = spawn_dungeon(
+            &mut cmd,
+            &templates,
+            player_template,
+            Some(&asset_server),
+            &options,
+            seed,
+        );
+        cmd.entity(player).insert(MovingPlayer {});
 
         for mut c in cameras.iter_mut() {
             let z = c.translation.z;
-            let new_pos = options.to_world_position(info.camera_focus).extend(z);
+            let new_pos = options.to_world_position(player_start).extend(z);
             c.translation = new_pos;
         }
 
-        let inventory_themes: Vec<_> = inventory_themes.iter().map(|(_, it)| it).collect();
+        let inventory_themes = assets_sorted(&inventory_themes);
         bevy::log::info!("inventory theme count: {}", inventory_themes.len());
 
+        // NOTE: cosmetic choice, kept out of the game rng
+        let mut rng = StdRng::seed_from_u64(seed);
         let inventory_theme = inventory_themes[rng.gen_range(0..inventory_themes.len())];
         cmd.insert_resource(InventoryAssets {
             slot: asset_server.load(inventory_theme.slot.as_str()),
@@ -286,111 +365,30 @@ impl<T: StateNext> RoguelikePlugin<T> {
             font: asset_server.load("fonts/pixeled.ttf"),
         });
 
-        let map_themes: Vec<_> = map_themes.iter().map(|(_, it)| it).collect();
-        let map_theme = map_themes[rng.gen_range(0..map_themes.len())];
-        let map_id = cmd
-            .spawn((SpatialBundle::default(), Name::new("RogueMap")))
-            .with_children(|rogue_map| {
-                for (pt, tile) in map.enumerate() {
-                    let texture = asset_server.load(
-                        match tile {
-                            Tile::Wall => {
-                                map_theme.wall[rng.gen_range(0..map_theme.wall.len())].clone()
-                            }
-                            Tile::Floor => {
-                                map_theme.floor[rng.gen_range(0..map_theme.floor.len())].clone()
-                            }
-                        }
-                        .as_str(),
-                    );
-                    rogue_map.spawn((
-                        Name::new(format!("Tile {}", pt)),
-                        Vector2D::from(pt),
-                        RenderInfo {
-                            texture,
-                            cosmetic_textures: vec![],
-                            z: 0.,
-                        },
-                        match tile {
-                            Tile::Wall => MapTile { is_passable: false },
-                            Tile::Floor => MapTile { is_passable: true },
-                        },
-                    ));
-                }
-            })
-            .id();
-
-        let item_templates: Vec<_> = item_templates.iter().map(|(_, it)| it).collect();
-        let items_id = cmd
-            .spawn((SpatialBundle::default(), Name::new("Items")))
-            .with_children(|cb| {
-                for ipt in info.item_spawns.clone() {
-                    let template = item_templates[rng.gen_range(0..item_templates.len())];
-                    let quality = Quality::roll(&mut rng);
-                    let mut ecmd = cb.spawn(Vector2D::from(ipt));
-                    spawn_item(
-                        &mut ecmd,
-                        asset_server.clone(),
-                        template,
-                        &quality,
-                        &mut rng,
-                    );
-                }
-            })
-            .id();
-
-        let combat_settings: Vec<_> = combat_settings.iter().map(|(_, it)| it).collect();
-        bevy::log::info!("combat settings count: {}", combat_settings.len());
-        let combat_settings = combat_settings[0];
-
-        if let Some(player_template) =
-            actor_templates.get(&asset_server.load("actors/human.actor.ron"))
-        {
-            let team_player = 1;
-            cmd.spawn((
-                Actor::new(
-                    asset_server.clone(),
-                    player_template,
-                    combat_settings,
-                    team_player,
-                    info.player_start,
+        cmd.spawn((
+            Name::new("HudRunInfo"),
+            HudRunInfo {},
+            TextBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    position: UiRect {
+                        left: Val::Px(8.0),
+                        top: Val::Px(8.0),
+                        ..default()
+                    },
+                    ..default()
+                },
+                text: Text::from_section(
+                    format!("seed {} depth {}", seed, Dungeon::default().depth),
+                    TextStyle {
+                        font: asset_server.load("fonts/pixeled.ttf"),
+                        font_size: 12.0,
+                        color: Color::WHITE,
+                    },
                 ),
-                MovingPlayer {},
-            ));
-        } else {
-            bevy::log::error!("human actor template not found");
-        }
-
-        let actor_templates: Vec<_> = actor_templates.iter().map(|(_, it)| it).collect();
-        let enemies_id = cmd
-            .spawn((SpatialBundle::default(), Name::new("Enemies")))
-            .with_children(|enms| {
-                for mpt in info.monster_spawns.clone() {
-                    let monster_template = actor_templates[rng.gen_range(0..actor_templates.len())];
-                    let team_monster = 1 + rng.gen_range(2..4);
-                    enms.spawn((
-                        Actor::new(
-                            asset_server.clone(),
-                            monster_template,
-                            combat_settings,
-                            team_monster,
-                            mpt,
-                        ),
-                        MovingFovRandom {},
-                    ));
-                }
-            })
-            .id();
-
-        cmd.insert_resource(RogueMap(map));
-        cmd.insert_resource(info);
-        cmd.insert_resource(RogueRng(rng));
-
-        cmd.insert_resource(MapEntities {
-            map_id,
-            enemies_id,
-            items_id,
-        });
+                ..default()
+            },
+        ));
 
         state.set_next();
     }
@@ -1,4 +1,5 @@
 use crate::components::*;
+use bevy::prelude::AssetServer;
 use bevy::reflect::TypeUuid;
 use bevy_inventory_ui::EquipmentDisplay;
 use bevy_roguelike_combat::*;
@@ -15,6 +16,17 @@ pub struct ActorTemplate {
     pub damage: DamageList<RogueDamageKind, RogueAttributeType>,
     pub equipment_display: EquipmentDisplay<RogueItemType>,
     pub inventory_capacity: usize,
+    /// faction the actor belongs to ([`crate::resources::Factions`])
+    pub faction: String,
+    /// shallowest dungeon floor the actor spawns on as a monster
+    #[serde(default)]
+    pub min_depth: u32,
+    /// how the actor acts as a monster ([`Ai`]). chases, hoards and wanders if empty.
+    #[serde(default)]
+    pub behaviours: Vec<(Behaviour, f32)>,
+    /// how long the actor remembers where it saw enemies and items as a monster ([`Memory`])
+    #[serde(default)]
+    pub memory: Memory,
     // TODO: initial equipment
     // TODO: initial inventory
 }
@@ -25,3 +37,18 @@ pub struct ActorRenderInfo {
     pub texture_path: String,
     pub texture_path_cosmetics: Vec<String>,
 }
+
+impl ActorRenderInfo {
+    /// textures of the actor rendered on the map
+    pub fn load(&self, asset_server: &AssetServer) -> RenderInfo {
+        RenderInfo {
+            texture: asset_server.load(self.texture_path.as_str()),
+            cosmetic_textures: self
+                .texture_path_cosmetics
+                .iter()
+                .map(|p| asset_server.load(p.as_str()))
+                .collect(),
+            z: 2.,
+        }
+    }
+}
@@ -0,0 +1,41 @@
+use super::MapInfo;
+use bevy::{prelude::*, utils::HashMap};
+use map_generator::{BitGrid, Map};
+
+/// Root entities of a spawned dungeon floor
+#[derive(Resource, Debug, Clone, Copy)]
+pub struct MapEntities {
+    pub map_id: Entity,
+    pub enemies_id: Entity,
+    pub items_id: Entity,
+}
+
+/// Dungeon floor the player is not on. Its entities are hidden and taken out of play
+/// (no [`crate::components::Vector2D`]) until the player comes back.
+#[derive(Debug, Clone)]
+pub struct DungeonFloor {
+    pub map: Map,
+    pub info: MapInfo,
+    pub entities: MapEntities,
+    /// tiles the player explored on the floor
+    pub tiles_revealed: BitGrid,
+    pub monsters: Vec<(Entity, IVec2)>,
+    pub items: Vec<(Entity, IVec2)>,
+}
+
+/// Dungeon depth system. Floors are connected with stairs and kept while the player is away.
+#[derive(Resource, Debug)]
+pub struct Dungeon {
+    /// current floor starting with 1
+    pub depth: u32,
+    pub floors: HashMap<u32, DungeonFloor>,
+}
+
+impl Default for Dungeon {
+    fn default() -> Self {
+        Self {
+            depth: 1,
+            floors: HashMap::default(),
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use super::Factions;
+use crate::components::{Faction, Relation};
+use bevy::prelude::Resource;
+use serde::{Deserialize, Serialize};
+
+/// Factions of the running game and their current relations.
+/// Built from [`Factions`] when the dungeon is spawned, relations change as the game goes (neutrals provoked).
+#[derive(Resource, Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FactionRelations {
+    /// faction names by [`Faction::id`]
+    names: Vec<String>,
+    /// relation of every faction pair, row by row
+    relations: Vec<Relation>,
+    default: Relation,
+    provoke_neutrals: bool,
+}
+
+impl FactionRelations {
+    /// Factions named in `factions` and in `names` (factions of actor templates)
+    pub fn new<'a>(factions: &'a Factions, names: impl IntoIterator<Item = &'a str>) -> Self {
+        let mut faction_names: Vec<String> = vec![];
+        for name in factions
+            .relations
+            .iter()
+            .flat_map(|(a, b, _)| [a.as_str(), b.as_str()])
+            .chain(names)
+        {
+            if !faction_names.iter().any(|n| n == name) {
+                faction_names.push(name.to_string());
+            }
+        }
+        let count = faction_names.len();
+        let mut relations = Self {
+            names: faction_names,
+            relations: vec![factions.default; count * count],
+            default: factions.default,
+            provoke_neutrals: factions.provoke_neutrals,
+        };
+        for (a, b, relation) in factions.relations.iter() {
+            if let (Some(a), Some(b)) = (relations.faction(a), relations.faction(b)) {
+                relations.set_relation(a, b, *relation);
+            }
+        }
+        relations
+    }
+
+    pub fn faction(&self, name: &str) -> Option<Faction> {
+        self.names
+            .iter()
+            .position(|n| n == name)
+            .map(|id| Faction::new(id as u32))
+    }
+
+    /// empty for unknown factions
+    pub fn name(&self, faction: Faction) -> &str {
+        self.names
+            .get(faction.id() as usize)
+            .map_or("", |n| n.as_str())
+    }
+
+    fn index(&self, a: Faction, b: Faction) -> Option<usize> {
+        let count = self.names.len();
+        let (a, b) = (a.id() as usize, b.id() as usize);
+        (a < count && b < count).then_some(a * count + b)
+    }
+
+    /// same faction is allied, unknown ones get the default relation
+    pub fn relation(&self, a: Faction, b: Faction) -> Relation {
+        if a == b {
+            return Relation::Allied;
+        }
+        self.index(a, b)
+            .map_or(self.default, |idx| self.relations[idx])
+    }
+
+    pub fn is_hostile(&self, a: Faction, b: Faction) -> bool {
+        self.relation(a, b) == Relation::Hostile
+    }
+
+    pub fn is_allied(&self, a: Faction, b: Faction) -> bool {
+        self.relation(a, b) == Relation::Allied
+    }
+
+    /// sets the relation both ways. relation of a faction to itself does not change.
+    pub fn set_relation(&mut self, a: Faction, b: Faction, relation: Relation) {
+        if a == b {
+            return;
+        }
+        if let (Some(ab), Some(ba)) = (self.index(a, b), self.index(b, a)) {
+            self.relations[ab] = relation;
+            self.relations[ba] = relation;
+        }
+    }
+
+    /// see [`Factions::provoke_neutrals`]
+    pub fn provokes_neutrals(&self) -> bool {
+        self.provoke_neutrals
+    }
+}
@@ -0,0 +1,16 @@
+use crate::components::Relation;
+use bevy::reflect::TypeUuid;
+use serde::{Deserialize, Serialize};
+
+/// Factions actors belong to (`faction` in actor templates) and how they treat each other
+#[derive(Debug, Clone, Serialize, Deserialize, TypeUuid)]
+#[uuid = "3c2f6a4e-8d1b-4f57-9a0e-6b7c2d9e1f48"]
+pub struct Factions {
+    /// relation between factions not listed in `relations` (actors of the same faction are always allied)
+    pub default: Relation,
+    /// relations between two factions (both ways)
+    pub relations: Vec<(String, String, Relation)>,
+    /// attacking a neutral actor turns its faction hostile to the attacker's
+    #[serde(default)]
+    pub provoke_neutrals: bool,
+}
@@ -0,0 +1,24 @@
+use bevy::{prelude::*, utils::HashMap};
+use map_generator::BitGrid;
+
+/// Field of view results shared by every query with the same origin and radius.
+/// Cleared when the map changes (doors opening, floor switch) and when a new turn starts.
+#[derive(Resource, Debug, Default)]
+pub struct FovCache {
+    visible: HashMap<(IVec2, i32), BitGrid>,
+}
+
+impl FovCache {
+    pub fn get(&self, origin: IVec2, radius: i32) -> Option<&BitGrid> {
+        self.visible.get(&(origin, radius))
+    }
+    pub fn contains(&self, origin: IVec2, radius: i32) -> bool {
+        self.visible.contains_key(&(origin, radius))
+    }
+    pub fn insert(&mut self, origin: IVec2, radius: i32, visible: BitGrid) {
+        self.visible.insert((origin, radius), visible);
+    }
+    pub fn clear(&mut self) {
+        self.visible.clear();
+    }
+}
@@ -1,4 +1,4 @@
-use crate::components::{RogueAttributeType, RogueDamageKind};
+use crate::components::{LightSource, RogueAttributeType, RogueDamageKind};
 use bevy::reflect::TypeUuid;
 use bevy_roguelike_combat::*;
 use serde::{Deserialize, Serialize};
@@ -15,11 +15,27 @@ pub enum ItemTemplate {
     Amulet(Amulet),
     Ring(Ring),
 }
+impl ItemTemplate {
+    pub fn render(&self) -> &ItemRenderInfo {
+        match self {
+            ItemTemplate::Weapon(Weapon { render, .. })
+            | ItemTemplate::Shield(Shield { render, .. })
+            | ItemTemplate::Helm(Helm { render, .. })
+            | ItemTemplate::Armor(Armor { render, .. })
+            | ItemTemplate::Boots(Boots { render, .. })
+            | ItemTemplate::Amulet(Amulet { render, .. })
+            | ItemTemplate::Ring(Ring { render, .. }) => render,
+        }
+    }
+}
 #[derive(Serialize, Deserialize)]
 pub struct ItemRenderInfo {
     pub name: String,
     pub texture_path: String,
     pub texture_equiped_path: Option<String>,
+    /// glowing items light their tile (or the tile of the actor who has them equiped)
+    #[serde(default)]
+    pub light: Option<LightSource>,
 }
 #[derive(Serialize, Deserialize)]
 pub struct ItemDefense {
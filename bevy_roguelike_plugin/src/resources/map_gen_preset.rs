@@ -0,0 +1,87 @@
+use bevy::reflect::TypeUuid;
+use map_generator::*;
+use serde::{Deserialize, Serialize};
+
+/// Describes which generators and passes (with which parameters) are used to generate a map.
+#[derive(Serialize, Deserialize, TypeUuid)]
+#[uuid = "f49167b0-95e5-4201-946a-0c769a11fd02"]
+pub struct MapGenPreset {
+    pub name: String,
+    /// steps are applied in order. first one should generate the base map.
+    pub steps: Vec<MapGenStep>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum MapGenStep {
+    /// generates a separate map and copies masked tiles on top
+    Generate {
+        generator: GeneratorPreset,
+        #[serde(default)]
+        mask: MapMask,
+    },
+    /// applies a post processing pass, keeping only masked changes
+    Modify {
+        modifier: ModifierPreset,
+        #[serde(default)]
+        mask: MapMask,
+    },
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GeneratorPreset {
+    Random,
+    Empty,
+    Drunkard(DrunkardGenerator),
+    Rooms(RoomsGenerator),
+    ConwayLife(ConwayLifeGenerator),
+    Bsp(BspGenerator),
+    Wfc(WfcGenerator),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum ModifierPreset {
+    Drunkard(DrunkardGenerator),
+    ConwayLife(ConwayLifeGenerator),
+    EnsureConnected(EnsureConnected),
+}
+
+impl MapGenPreset {
+    pub fn to_pipeline(&self) -> MapPipeline {
+        self.steps
+            .iter()
+            .fold(MapPipeline::default(), |pipeline, step| {
+                match step.clone() {
+                    MapGenStep::Generate { generator, mask } => {
+                        pipeline.blend(generator.to_generator(), mask)
+                    }
+                    MapGenStep::Modify { modifier, mask } => {
+                        pipeline.then_masked(modifier.to_modifier(), mask)
+                    }
+                }
+            })
+    }
+}
+
+impl GeneratorPreset {
+    pub fn to_generator(self) -> Box<dyn MapGenerator> {
+        match self {
+            GeneratorPreset::Random => Box::new(RandomMapGenerator {}),
+            GeneratorPreset::Empty => Box::new(EmptyGenerator {}),
+            GeneratorPreset::Drunkard(g) => Box::new(g),
+            GeneratorPreset::Rooms(g) => Box::new(g),
+            GeneratorPreset::ConwayLife(g) => Box::new(g),
+            GeneratorPreset::Bsp(g) => Box::new(g),
+            GeneratorPreset::Wfc(g) => Box::new(g),
+        }
+    }
+}
+
+impl ModifierPreset {
+    pub fn to_modifier(self) -> Box<dyn MapModifier> {
+        match self {
+            ModifierPreset::Drunkard(m) => Box::new(m),
+            ModifierPreset::ConwayLife(m) => Box::new(m),
+            ModifierPreset::EnsureConnected(m) => Box::new(m),
+        }
+    }
+}
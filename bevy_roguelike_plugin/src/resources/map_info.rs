@@ -1,59 +1,162 @@
+use super::SpawnOptions;
 use bevy::{math::IVec2, prelude::Resource};
 use map_generator::*;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
 
 #[cfg(feature = "debug")]
 use colored::Colorize;
 
-#[derive(Resource, Debug, Clone)]
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
 pub struct MapInfo {
     pub player_start: IVec2,
     pub camera_focus: IVec2,
     pub monster_spawns: Vec<IVec2>,
     pub item_spawns: Vec<IVec2>,
+    /// farthest (in steps) reachable floor tile from the player start
+    pub exit: IVec2,
+    /// walls holding a torch (see [`super::LightingOptions`])
+    #[serde(default)]
+    pub torches: Vec<IVec2>,
 }
 
 impl MapInfo {
-    pub fn from_map(map: &Map, rng: &mut StdRng) -> MapInfo {
-        let floor: Vec<IVec2> = map
+    /// `depth` of the dungeon floor starting with 1. deeper floors have more monsters.
+    /// `prefabs` get their guaranteed spawns, random spawns, player start and exit are kept out of them.
+    /// `None` if there is no floor to start on.
+    pub fn from_map(
+        map: &Map,
+        options: &SpawnOptions,
+        depth: u32,
+        prefabs: &[PrefabPlacement],
+        rng: &mut StdRng,
+    ) -> Option<MapInfo> {
+        let in_prefab = |pt: IVec2| prefabs.iter().any(|p| p.rect.contains(pt));
+        // NOTE: only the largest region, so everything spawns reachable from the player start
+        // and only on plain floor (not in water, lava, on stairs...)
+        let regions = MapRegions::from_map(map);
+        let largest = regions.largest();
+        let floor: Vec<IVec2> = largest
+            .map(|r| {
+                regions
+                    .points(r)
+                    .filter(|pt| map[*pt] == Tile::Floor && !in_prefab(*pt))
+                    .collect()
+            })
+            .unwrap_or_default();
+
+        let player_start = *floor.choose(rng)?;
+        // NOTE: distance in steps (not movement cost), the way the safe radius is measured
+        let distances =
+            DijkstraMap::with_cost(map.size(), [(player_start, 0)], Neighborhood::Four, |pt| {
+                map[pt].is_passable().then_some(1)
+            });
+        // NOTE: the exit becomes stairs, so it is never placed over doors, water, lava...
+        let (exit, distance_max) = distances
             .enumerate()
-            .filter(|(_, t)| **t == Tile::Floor)
-            .map(|(p, _)| p)
-            .collect();
+            .filter(|(pt, _)| map[*pt] == Tile::Floor && !in_prefab(*pt))
+            .max_by_key(|(_, d)| *d)
+            .unwrap_or((player_start, 0));
 
-        let pidx = rng.gen_range(0..floor.len());
-        let monster_count = floor.len() / 32;
-        let item_count = floor.len() / 64;
-        let player_start = floor[pidx];
+        let monster_ratio = options.monster_ratio
+            + options.monster_ratio_per_depth * depth.saturating_sub(1) as f32;
+        let monster_count = (floor.len() as f32 * monster_ratio) as usize;
+        let item_count = (floor.len() as f32 * options.item_ratio) as usize;
 
-        let mut monster_spawns = Vec::new();
-        while monster_spawns.len() < monster_count {
-            let midx = rng.gen_range(0..floor.len());
-            let pt = floor[midx];
-            if midx != pidx && !monster_spawns.contains(&pt) {
-                monster_spawns.push(pt);
-            }
-        }
+        // NOTE: monster density grows linearly with the distance from the player start
+        let monster_candidates: Vec<_> = distances
+            .enumerate()
+            .filter(|(pt, d)| {
+                *d > options.safe_radius
+                    && *pt != exit
+                    && map[*pt] == Tile::Floor
+                    && !in_prefab(*pt)
+            })
+            .collect();
+        let distance_span = i32::max(distance_max - options.safe_radius, 1) as f32;
+        let monster_spawns = choose_weighted(rng, &monster_candidates, monster_count, |(_, d)| {
+            f32::max(
+                1. + (options.monster_distance_weight - 1.) * (d - options.safe_radius) as f32
+                    / distance_span,
+                0.,
+            )
+        });
 
-        let mut item_spawns = Vec::new();
-        while item_spawns.len() < item_count {
-            let iidx = rng.gen_range(0..floor.len());
-            let pt = floor[iidx];
-            if !item_spawns.contains(&pt) {
-                item_spawns.push(pt);
-            }
-        }
+        let item_candidates: Vec<_> = floor
+            .iter()
+            .filter(|pt| **pt != player_start && **pt != exit)
+            .copied()
+            .collect();
+        let item_spawns = choose_weighted(rng, &item_candidates, item_count, |pt| {
+            f32::max(
+                if map.is_dead_end(*pt) {
+                    options.item_dead_end_weight
+                } else if map.is_corner(*pt) {
+                    options.item_corner_weight
+                } else {
+                    1.
+                },
+                0.,
+            )
+        });
 
-        MapInfo {
+        // NOTE: prefab spawns are guaranteed as long as they are reachable
+        let is_reachable = |pt: &IVec2| largest.is_some() && regions.get(*pt) == largest;
+        let prefab_monster_spawns = prefabs
+            .iter()
+            .flat_map(|p| p.monster_spawns.iter())
+            .filter(|pt| is_reachable(pt));
+        let prefab_item_spawns = prefabs
+            .iter()
+            .flat_map(|p| p.item_spawns.iter())
+            .filter(|pt| is_reachable(pt));
+
+        Some(MapInfo {
             player_start,
             camera_focus: player_start,
-            monster_spawns,
-            item_spawns,
+            monster_spawns: monster_spawns
+                .into_iter()
+                .map(|(pt, _)| pt)
+                .chain(prefab_monster_spawns.copied())
+                .collect(),
+            item_spawns: item_spawns
+                .into_iter()
+                .chain(prefab_item_spawns.copied())
+                .collect(),
+            exit,
+            torches: vec![],
+        })
+    }
+
+    /// player starts (and exits) at `pt`, nothing spawns
+    pub fn at(pt: IVec2) -> MapInfo {
+        MapInfo {
+            player_start: pt,
+            camera_focus: pt,
+            monster_spawns: vec![],
+            item_spawns: vec![],
+            exit: pt,
+            torches: vec![],
         }
     }
 
     pub fn to_colorized_string(&self) -> String {
-        format!("player star: {}", self.player_start)
+        format!("player star: {}, exit: {}", self.player_start, self.exit)
+    }
+}
+
+fn choose_weighted<T: Copy>(
+    rng: &mut StdRng,
+    candidates: &[T],
+    amount: usize,
+    weight: impl Fn(&T) -> f32,
+) -> Vec<T> {
+    match candidates.choose_multiple_weighted(rng, amount, weight) {
+        Ok(chosen) => chosen.copied().collect(),
+        Err(err) => {
+            bevy::log::error!("could not choose spawn locations. {}", err);
+            Vec::new()
+        }
     }
 }
 
@@ -69,6 +172,14 @@ impl Colorized for Tile {
             match self {
                 Tile::Wall => "#".bright_red(),
                 Tile::Floor => ".".bright_green(),
+                Tile::Door(DoorState::Open) => "/".yellow(),
+                Tile::Door(_) => "+".yellow(),
+                Tile::ShallowWater => "~".bright_blue(),
+                Tile::DeepWater => "~".blue(),
+                Tile::Lava => "~".red(),
+                Tile::Rubble => ":".white(),
+                Tile::StairsUp => "<".bright_white(),
+                Tile::StairsDown => ">".bright_white(),
             }
         )
     }
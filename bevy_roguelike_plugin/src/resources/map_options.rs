@@ -1,4 +1,6 @@
+use crate::components::LightSource;
 use bevy::prelude::*;
+use map_generator::{FovOptions, LightMap};
 use serde::{Deserialize, Serialize};
 
 /// Map generation options. Must be used as a resource
@@ -9,6 +11,107 @@ pub struct MapOptions {
     pub map_size: IVec2,
     /// Tile world size
     pub tile_size: f32,
+    /// Monster, item and exit placement
+    pub spawn: SpawnOptions,
+    /// Asset path of the [`super::MapGenPreset`] to use. Picked at random from loaded presets if not set.
+    pub map_gen_preset: Option<String>,
+    /// Seed of the run (see [`super::RunSeed`]). A random one is picked (and logged) if not set.
+    pub seed: Option<u64>,
+    /// Field of view algorithm and which walls it lights
+    pub fov: FovOptions,
+    /// Light sources and sight in the dark
+    pub lighting: LightingOptions,
+}
+
+/// Options driving [`super::MapInfo`] spawn placement
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpawnOptions {
+    /// No monsters are spawned closer (in steps) to the player start
+    pub safe_radius: i32,
+    /// Monster count per floor tile
+    pub monster_ratio: f32,
+    /// Item count per floor tile
+    pub item_ratio: f32,
+    /// How many times more likely a monster spawns at the farthest tile than right outside of the safe radius
+    pub monster_distance_weight: f32,
+    /// Item spawn weight of a dead end (normal floor is 1.)
+    pub item_dead_end_weight: f32,
+    /// Item spawn weight of a room corner (normal floor is 1.)
+    pub item_corner_weight: f32,
+    /// Monster count per floor tile added with every dungeon floor below the first one
+    pub monster_ratio_per_depth: f32,
+    /// Item quality roll bonus (out of 100) added with every dungeon floor below the first one (negative is a penalty)
+    pub item_quality_per_depth: i32,
+    /// How many [`super::PrefabAsset`] rooms are stamped into every map (less if they do not fit)
+    pub prefab_count: usize,
+}
+
+impl Default for SpawnOptions {
+    fn default() -> Self {
+        Self {
+            safe_radius: 8,
+            monster_ratio: 1. / 32.,
+            item_ratio: 1. / 64.,
+            monster_distance_weight: 4.,
+            item_dead_end_weight: 8.,
+            item_corner_weight: 4.,
+            monster_ratio_per_depth: 1. / 256.,
+            item_quality_per_depth: 4,
+            prefab_count: 2,
+        }
+    }
+}
+
+/// Options of tile light levels (see [`super::RogueLightMap`])
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LightingOptions {
+    /// Everything is lit (only the field of view radius limits sight) if disabled
+    pub enabled: bool,
+    /// Light level of tiles no light reaches
+    pub ambient: f32,
+    /// Tiles lit less than this are only seen up close
+    pub lit_threshold: f32,
+    /// How far actors see unlit tiles
+    pub dark_sight_radius: i32,
+    /// Minimal distance between torches placed on walls of every floor (no torches if 0)
+    pub torch_spacing: i32,
+    pub torch: LightSource,
+    /// Light of a fire damage hit on the defender tile
+    pub fire_flash: LightSource,
+    /// How many turns a fire hit stays lit
+    pub fire_flash_turns: u32,
+}
+
+impl Default for LightingOptions {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            ambient: 0.,
+            lit_threshold: 0.1,
+            dark_sight_radius: 2,
+            torch_spacing: 8,
+            torch: LightSource {
+                radius: 6,
+                intensity: 1.,
+            },
+            fire_flash: LightSource {
+                radius: 3,
+                intensity: 0.8,
+            },
+            fire_flash_turns: 2,
+        }
+    }
+}
+
+impl LightingOptions {
+    /// Whether a tile in the field of view from `origin` is seen:
+    /// lit enough or close enough to be seen in the dark.
+    pub fn can_see(&self, light_map: &LightMap, origin: IVec2, pt: IVec2) -> bool {
+        let d = pt - origin;
+        let radius = self.dark_sight_radius;
+        d.x * d.x + d.y * d.y <= radius * radius + radius
+            || light_map.level(pt) >= self.lit_threshold
+    }
 }
 
 impl Default for MapOptions {
@@ -16,6 +119,11 @@ impl Default for MapOptions {
         Self {
             map_size: IVec2::new(80, 50),
             tile_size: 32.0,
+            spawn: SpawnOptions::default(),
+            map_gen_preset: None,
+            seed: None,
+            fov: FovOptions::default(),
+            lighting: LightingOptions::default(),
         }
     }
 }
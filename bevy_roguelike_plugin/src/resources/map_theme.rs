@@ -1,4 +1,5 @@
 use bevy::reflect::TypeUuid;
+use map_generator::Tile;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, TypeUuid)]
@@ -6,4 +7,43 @@ use serde::{Deserialize, Serialize};
 pub struct MapTheme {
     pub floor: Vec<String>,
     pub wall: Vec<String>,
+    // NOTE: optional terrain textures. floor or wall is used when missing
+    #[serde(default)]
+    pub door: Vec<String>,
+    #[serde(default)]
+    pub shallow_water: Vec<String>,
+    #[serde(default)]
+    pub deep_water: Vec<String>,
+    #[serde(default)]
+    pub lava: Vec<String>,
+    #[serde(default)]
+    pub rubble: Vec<String>,
+    #[serde(default)]
+    pub stairs_up: Vec<String>,
+    #[serde(default)]
+    pub stairs_down: Vec<String>,
+}
+
+impl MapTheme {
+    /// texture variants of the tile
+    pub fn textures(&self, tile: Tile) -> &[String] {
+        let textures = match tile {
+            Tile::Wall => &self.wall,
+            Tile::Floor => &self.floor,
+            Tile::Door(_) => &self.door,
+            Tile::ShallowWater => &self.shallow_water,
+            Tile::DeepWater => &self.deep_water,
+            Tile::Lava => &self.lava,
+            Tile::Rubble => &self.rubble,
+            Tile::StairsUp => &self.stairs_up,
+            Tile::StairsDown => &self.stairs_down,
+        };
+        if !textures.is_empty() {
+            textures
+        } else if tile.is_passable() {
+            &self.floor
+        } else {
+            &self.wall
+        }
+    }
 }
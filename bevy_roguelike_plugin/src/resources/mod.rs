@@ -1,19 +1,37 @@
 pub use actor_template::ActorRenderInfo;
 pub use actor_template::ActorTemplate;
 pub use combat_settings::CombatSettings;
+pub use dungeon::*;
+pub use faction_relations::FactionRelations;
+pub use factions::Factions;
+pub use fov_cache::FovCache;
 pub use inventory_assets::InventoryAssets;
 pub use inventory_assets::InventoryTheme;
 pub use item_template::*;
+pub use map_gen_preset::*;
 pub use map_info::*;
 pub use map_options::*;
 pub use map_theme::MapTheme;
+pub use prefab_asset::PrefabAsset;
+pub use rogue_light_map::RogueLightMap;
 pub use rogue_map::RogueMap;
+pub use run_seed::RunSeed;
+pub use save_game::*;
 
 mod actor_template;
 mod combat_settings;
+mod dungeon;
+mod faction_relations;
+mod factions;
+mod fov_cache;
 mod inventory_assets;
 mod item_template;
+mod map_gen_preset;
 mod map_info;
 mod map_options;
 mod map_theme;
+mod prefab_asset;
+mod rogue_light_map;
 mod rogue_map;
+mod run_seed;
+mod save_game;
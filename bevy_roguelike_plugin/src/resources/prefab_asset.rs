@@ -0,0 +1,9 @@
+use bevy::reflect::TypeUuid;
+use map_generator::Prefab;
+use serde::{Deserialize, Serialize};
+
+/// Hand authored [`Prefab`] room stamped into generated maps.
+#[derive(Serialize, Deserialize, TypeUuid)]
+#[serde(transparent)]
+#[uuid = "5d0b6a3e-8f7c-4b8e-9d4e-2a61c7f3b915"]
+pub struct PrefabAsset(pub Prefab);
@@ -0,0 +1,15 @@
+use bevy::prelude::Resource;
+use map_generator::LightMap;
+use std::ops::Deref;
+
+/// Light level of every tile on the current floor. Recomputed when light sources move, appear or go out.
+#[derive(Resource, Default)]
+pub struct RogueLightMap(pub LightMap);
+
+impl Deref for RogueLightMap {
+    type Target = LightMap;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
@@ -0,0 +1,6 @@
+use bevy::prelude::*;
+
+/// Seed every random draw of the run flows from (map generation, spawning, item quality, combat).
+/// Two runs with the same seed and the same inputs play out the same.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RunSeed(pub u64);
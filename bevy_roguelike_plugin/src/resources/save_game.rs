@@ -0,0 +1,106 @@
+use super::{FactionRelations, MapInfo};
+use crate::components::*;
+use bevy::math::IVec2;
+use bevy_inventory_ui::EquipmentDisplay;
+use bevy_roguelike_combat::*;
+use map_generator::Map;
+use serde::{Deserialize, Serialize};
+
+/// Saved running game (see [`crate::systems::save`]).
+/// Items are referenced by their index in [`SaveGame::items`] instead of entity ids.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SaveGame {
+    /// seed of the run ([`super::RunSeed`])
+    pub seed: u64,
+    /// game rng state, so the loaded game rolls the same numbers as the saved one would
+    pub rng: RogueRng,
+    /// floor the player is on
+    pub depth: u32,
+    /// turns passed ([`bevy_roguelike_turns::TurnScheduler::turn`])
+    #[serde(default)]
+    pub turn: u64,
+    pub floors: Vec<SavedFloor>,
+    pub player: SavedActor,
+    pub items: Vec<SavedItem>,
+    /// factions and their relations at the time of saving
+    pub factions: FactionRelations,
+}
+
+/// Dungeon floor with its monsters and items (lying on the floor or carried by actors on it)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedFloor {
+    pub depth: u32,
+    pub map: Map,
+    pub info: MapInfo,
+    /// texture of every tile in [`Map::enumerate`] order. empty if saved without rendering.
+    pub tile_textures: Vec<String>,
+    /// tiles the player explored while on the floor. empty for the floor the player is on (see [`FieldOfView`])
+    pub tiles_revealed: Vec<IVec2>,
+    pub monsters: Vec<SavedActor>,
+    pub items: Vec<usize>,
+}
+
+/// What moves the actor around
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SavedController {
+    #[default]
+    None,
+    Player,
+    Ai(Ai),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedActor {
+    pub name: String,
+    pub faction: Faction,
+    pub controller: SavedController,
+    /// what the monster remembers ([`Memory`])
+    #[serde(default)]
+    pub memory: Option<Memory>,
+    pub position: IVec2,
+    pub turn_state: TurnState,
+    /// tie breaker of equally ready actors ([`bevy_roguelike_turns::TurnScheduler::order`]).
+    /// `None` if not seen by the scheduler yet.
+    #[serde(default)]
+    pub turn_order: Option<u64>,
+    pub attributes: Attributes<RogueAttributeType>,
+    pub action_points: ActionPoints<RogueAttributeType>,
+    pub hit_points: HitPoints<RogueAttributeType>,
+    pub damage: DamageList<RogueDamageKind, RogueAttributeType>,
+    pub protection: Protection<RogueDamageKind, RogueAttributeType>,
+    pub evasion: Evasion<RogueAttributeType>,
+    pub resistance: Resistance<RogueDamageKind>,
+    pub stats: StatsComputed<RogueDamageKind, RogueAttributeType>,
+    pub field_of_view: FieldOfView,
+    pub equipment_display: EquipmentDisplay<RogueItemType>,
+    pub equipment: Vec<((RogueItemType, u8), Option<usize>)>,
+    pub inventory: Vec<Option<usize>>,
+    pub render: Option<SavedRender>,
+}
+
+/// Item with its rolled stats
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedItem {
+    pub name: String,
+    pub item_type: RogueItemType,
+    pub quality: Quality,
+    /// position on the map, `None` if carried
+    pub position: Option<IVec2>,
+    pub attributes: Option<Attributes<RogueAttributeType>>,
+    pub protection: Option<Protection<RogueDamageKind, RogueAttributeType>>,
+    pub resistance: Option<Resistance<RogueDamageKind>>,
+    pub block: Option<Block<RogueDamageKind, RogueAttributeType>>,
+    pub damage: Option<Damage<RogueDamageKind, RogueAttributeType>>,
+    pub render: Option<SavedRender>,
+    pub render_equiped: Option<SavedRender>,
+    #[serde(default)]
+    pub light: Option<LightSource>,
+}
+
+/// [`RenderInfo`] (or [`RenderInfoEquiped`]) with asset paths instead of handles
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SavedRender {
+    pub texture: String,
+    pub cosmetic_textures: Vec<String>,
+    pub z: f32,
+}
@@ -1,56 +1,54 @@
-use crate::{components::*, events::*, resources::RogueMap};
-use bevy::{log, prelude::*, utils::HashMap};
+use crate::{
+    components::*,
+    events::*,
+    resources::{FactionRelations, MapEntities, RogueMap},
+};
+use bevy::{prelude::*, utils::HashMap};
 use bevy_inventory::{Equipment, Inventory, ItemType};
 use bevy_inventory_ui::InventoryDisplayOwner;
 use bevy_roguelike_combat::*;
+use bevy_roguelike_turns::ActionCosts;
 use map_generator::*;
 
 pub fn act(
-    actors: Query<(&Team, &Vector2D)>,
-    enemies: Query<(Entity, &Team, &Vector2D)>,
+    actors: Query<(Entity, &Faction, &Vector2D)>,
     // TODO: ActComponent instead of ActEvent
     mut act_reader: EventReader<ActEvent>,
     mut attack_writer: EventWriter<AttackEvent>,
     mut move_writer: EventWriter<MoveEvent>,
     mut idle_writer: EventWriter<IdleEvent>,
     map: Res<RogueMap>,
+    factions: Res<FactionRelations>,
 ) {
-    let team_pt: HashMap<_, _> = actors.iter().map(|(t, p)| (**p, *t)).collect();
+    let faction_pt: HashMap<_, _> = actors.iter().map(|(e, f, p)| (**p, (e, *f))).collect();
     for e in act_reader.iter() {
         if e.delta == IVec2::new(0, 0) {
             idle_writer.send(IdleEvent { id: e.id });
             continue;
         }
-        if let Ok((team, pt)) = actors.get(e.id) {
+        if let Ok((_, faction, pt)) = actors.get(e.id) {
             let dest = **pt + e.delta;
-            if !map.is_in_bounds(dest) || map[dest] != Tile::Floor {
+            if !map.is_in_bounds(dest) || !map[dest].is_passable() {
                 idle_writer.send(IdleEvent { id: e.id });
                 continue;
             }
-            if let Some(other_team) = team_pt.get(&dest) {
-                if *other_team == *team {
-                    // NOTE: can not move into a tile ocupied by a team mate
-                    idle_writer.send(IdleEvent { id: e.id });
-                    continue;
-                } else if let Some((enemy_entity, _, _)) =
-                    enemies.iter().find(|(_, t, p)| *t != team && ***p == dest)
-                {
-                    attack_writer.send({
-                        AttackEvent {
-                            attacker: e.id,
-                            defender: enemy_entity,
-                        }
+            match faction_pt.get(&dest) {
+                Some((other, other_faction)) if !factions.is_allied(*faction, *other_faction) => {
+                    // NOTE: neutrals are attacked too (only when bumped into on purpose)
+                    attack_writer.send(AttackEvent {
+                        attacker: e.id,
+                        defender: *other,
                     })
-                } else {
-                    log::error!("nothing to attack at {:?} (... has bugs).", dest);
                 }
-            } else {
-                move_writer.send(MoveEvent {
-                    actor: e.id,
-                    team: *team,
-                    from: **pt,
-                    to: dest,
-                });
+                _ => {
+                    // NOTE: allies swap places (see try_move)
+                    move_writer.send(MoveEvent {
+                        actor: e.id,
+                        faction: *faction,
+                        from: **pt,
+                        to: dest,
+                    });
+                }
             }
         }
     }
@@ -94,15 +92,54 @@ pub fn death_read<I: ItemType>(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 pub fn try_move(
-    mut actors: Query<(&mut Vector2D, &Team, &mut FieldOfView)>,
+    mut actors: Query<(Entity, &mut Vector2D, &Faction, &mut FieldOfView)>,
+    map_tiles: Query<&MapTiles>,
+    mut tiles: Query<&mut MapTile>,
     mut move_reader: EventReader<MoveEvent>,
     mut ap_spend_writer: EventWriter<SpendAPEvent>,
+    mut damage_writer: EventWriter<DamageHitPointsEvent>,
+    mut map: ResMut<RogueMap>,
+    map_entities: Res<MapEntities>,
+    factions: Res<FactionRelations>,
+    costs: Res<ActionCosts>,
 ) {
-    let mut team_pt: HashMap<_, _> = actors.iter().map(|(p, t, _)| (**p, *t)).collect();
+    let mut faction_pt: HashMap<_, _> = actors.iter().map(|(e, p, f, _)| (**p, (e, *f))).collect();
+    // NOTE: every actor entering a tile pays for it, opens doors and gets burned,
+    // the ally stepping back in a swap too
+    let mut enter = |map: &mut Map, actor: Entity, to: IVec2| {
+        let tile = map[to];
+        let cost = costs.movement * tile.movement_cost().unwrap_or(1) as i16;
+        ap_spend_writer.send(SpendAPEvent::new(actor, cost));
+        if tile == Tile::Door(DoorState::Closed) {
+            // NOTE: walking through a closed door leaves it open
+            map[to] = Tile::Door(DoorState::Open);
+            // NOTE: tiles of other dungeon floors share the same positions
+            let tile_id = map_tiles
+                .get(map_entities.map_id)
+                .ok()
+                .and_then(|t| t.get(to));
+            if let Some(mut map_tile) = tile_id.and_then(|id| tiles.get_mut(id).ok()) {
+                *map_tile = MapTile::from(map[to]);
+            }
+        }
+        let damage = tile.damage_on_enter();
+        if damage > 0 {
+            damage_writer.send(DamageHitPointsEvent {
+                defender: actor,
+                amount: damage,
+            });
+        }
+    };
     for e in move_reader.iter() {
-        if let Ok((mut pt, _tt, mut fov)) = actors.get_mut(e.actor) {
-            if let Some(_team) = team_pt.get(&e.to) {
+        let ally = match faction_pt.get(&e.to) {
+            Some((other, other_faction))
+                if *other != e.actor && factions.is_allied(e.faction, *other_faction) =>
+            {
+                Some((*other, *other_faction))
+            }
+            Some(_) => {
                 bevy::log::trace!(
                     "trying to move from {} to {} by {:?}. location already ocupied",
                     e.from,
@@ -111,10 +148,34 @@ pub fn try_move(
                 );
                 continue;
             }
-            ap_spend_writer.send(SpendAPEvent::new(e.actor, AP_MOVE_COST_DEFAULT));
-            team_pt.entry(e.to).insert(e.team);
+            None => None,
+        };
+        if ally.is_some() && !map[e.from].is_passable() {
+            bevy::log::trace!(
+                "ally at {} can not step back to {}. no swap by {:?}",
+                e.to,
+                e.from,
+                e.actor
+            );
+            continue;
+        }
+        if let Some((ally, ally_faction)) = ally {
+            // NOTE: allies do not block each other, the one standing in the way steps back
+            if let Ok((_, mut pt, _, mut fov)) = actors.get_mut(ally) {
+                *pt = Vector2D::from(e.from);
+                fov.is_dirty = true;
+                faction_pt.insert(e.from, (ally, ally_faction));
+                enter(&mut map, ally, e.from);
+            }
+        }
+        if let Ok((_, mut pt, _, mut fov)) = actors.get_mut(e.actor) {
+            if ally.is_none() {
+                faction_pt.remove(&e.from);
+            }
+            faction_pt.insert(e.to, (e.actor, e.faction));
             *pt = Vector2D::from(e.to);
             fov.is_dirty = true;
+            enter(&mut map, e.actor, e.to);
         }
     }
 }
@@ -1,4 +1,4 @@
-use crate::components::*;
+use crate::{components::*, resources::FactionRelations};
 use bevy::prelude::*;
 use bevy_inventory::{Equipment, ItemType};
 use bevy_inventory_ui::UiTextInfo;
@@ -7,23 +7,31 @@ use bevy_roguelike_combat::*;
 #[allow(clippy::type_complexity)]
 pub fn actors_fill_text_info(
     mut cmd: Commands,
-    players: Query<&FieldOfView, With<MovingPlayer>>,
+    players: Query<(&FieldOfView, &Faction), With<MovingPlayer>>,
     actors: Query<(
         Entity,
         &Name,
-        &Team,
+        &Faction,
         &ActionPoints<RogueAttributeType>,
         &HitPoints<RogueAttributeType>,
         &StatsComputed<RogueDamageKind, RogueAttributeType>,
         &Vector2D,
         Option<&UiTextInfo>,
     )>,
+    factions: Res<FactionRelations>,
 ) {
-    for player_fov in players.iter() {
-        for (actor_entity, name, team, ap, hp, stats, pt, info) in actors.iter() {
-            if player_fov.tiles_visible.iter().any(|t| *t == **pt) {
+    for (player_fov, player_faction) in players.iter() {
+        for (actor_entity, name, faction, ap, hp, stats, pt, info) in actors.iter() {
+            if player_fov.tiles_visible.contains(**pt) {
                 let mut titles_descriptions = vec![];
-                titles_descriptions.push(("Team".to_string(), format!("{}", team.id())));
+                titles_descriptions.push((
+                    "Faction".to_string(),
+                    format!(
+                        "{} ({:?})",
+                        factions.name(*faction),
+                        factions.relation(*player_faction, *faction)
+                    ),
+                ));
                 titles_descriptions.push(("Speed".to_string(), format!("{}", ap.increment())));
                 titles_descriptions.push(("Hit points".to_string(), hp.full().to_string()));
                 titles_descriptions
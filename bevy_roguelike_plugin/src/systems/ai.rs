@@ -0,0 +1,286 @@
+use crate::{
+    components::*,
+    events::*,
+    resources::{FactionRelations, RogueMap},
+    systems::pathfinding::Pathfinder,
+};
+use bevy::{
+    prelude::*,
+    utils::{HashMap, HashSet},
+};
+use bevy_inventory::{Inventory, ItemPickUpEvent};
+use bevy_roguelike_combat::*;
+use bevy_roguelike_turns::TurnScheduler;
+use rand::prelude::*;
+
+/// What a scored behaviour wants to do
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Plan {
+    Stay,
+    Wander,
+    Approach(IVec2),
+    Retreat,
+    PickUp,
+    GoForItems,
+}
+
+/// What a monster knows when scoring its behaviours
+struct Situation {
+    pt: IVec2,
+    home: IVec2,
+    hp_ratio: f32,
+    /// closest visible enemy
+    enemy: Option<IVec2>,
+    /// where an enemy was last seen ([`Memory`])
+    enemy_last_seen: Option<IVec2>,
+    /// closest enemy an ally (not too far away) is after
+    pack_enemy: Option<(IVec2, i32)>,
+    sees_item: bool,
+    is_on_item: bool,
+    is_inventory_full: bool,
+    /// where an item was last seen ([`Memory`])
+    item_last_seen: Option<IVec2>,
+}
+
+fn distance_squared(a: IVec2, b: IVec2) -> i32 {
+    let d = a - b;
+    d.x * d.x + d.y * d.y
+}
+
+/// `None` if the behaviour does not apply
+fn score(behaviour: &Behaviour, s: &Situation) -> Option<(f32, Plan)> {
+    match *behaviour {
+        Behaviour::Wander => Some((0.1, Plan::Wander)),
+        Behaviour::Guard { radius } => {
+            let d = distance_squared(s.pt, s.home);
+            if d > 4 * radius * radius {
+                Some((0.9, Plan::Approach(s.home)))
+            } else if d > radius * radius {
+                Some((0.6, Plan::Approach(s.home)))
+            } else {
+                Some((0.15, Plan::Stay))
+            }
+        }
+        Behaviour::Chase => match (s.enemy, s.enemy_last_seen) {
+            (Some(enemy), _) => Some((0.8, Plan::Approach(enemy))),
+            (None, Some(last_seen)) => Some((0.4, Plan::Approach(last_seen))),
+            _ => None,
+        },
+        Behaviour::Flee { hp_below } => {
+            (s.enemy.is_some() && s.hp_ratio < hp_below).then_some((1., Plan::Retreat))
+        }
+        Behaviour::KeepDistance { distance } => s.enemy.map(|enemy| {
+            let d = distance_squared(s.pt, enemy);
+            let plan = if d < distance * distance {
+                Plan::Retreat
+            } else if d > (distance + 1) * (distance + 1) {
+                Plan::Approach(enemy)
+            } else {
+                Plan::Stay
+            };
+            (0.85, plan)
+        }),
+        Behaviour::Pack { radius } => match (s.enemy, s.pack_enemy) {
+            (None, Some((enemy, mate_distance))) if mate_distance <= radius * radius => {
+                Some((0.7, Plan::Approach(enemy)))
+            }
+            _ => None,
+        },
+        Behaviour::Hoard => {
+            if s.is_inventory_full {
+                None
+            } else if s.is_on_item {
+                Some((0.5, Plan::PickUp))
+            } else if s.sees_item {
+                Some((0.5, Plan::GoForItems))
+            } else {
+                s.item_last_seen
+                    .map(|last_seen| (0.3, Plan::Approach(last_seen)))
+            }
+        }
+    }
+}
+
+/// closest enemy in the field of view
+fn closest_enemy(
+    pt: IVec2,
+    faction: Faction,
+    fov: &FieldOfView,
+    faction_pt: &HashMap<IVec2, Faction>,
+    factions: &FactionRelations,
+) -> Option<IVec2> {
+    // NOTE: bit grid iterates in row major order so equally close enemies are picked the same way.
+    let mut distance_last = i32::MAX;
+    let mut closest = None;
+    for pt_visible in fov.tiles_visible.iter() {
+        if let Some(other_faction) = faction_pt.get(&pt_visible) {
+            if factions.is_hostile(faction, *other_faction) {
+                let distance = distance_squared(pt_visible, pt);
+                if distance < distance_last {
+                    closest = Some(pt_visible);
+                    distance_last = distance;
+                }
+            }
+        }
+    }
+    closest
+}
+
+/// The acting monster scores its behaviours ([`Ai`]) and acts on the best one.
+/// Paths are found with [`Pathfinder`], items are reached following a flow field towards every item.
+#[allow(clippy::type_complexity)]
+pub fn input_ai(
+    mut rng: ResMut<RogueRng>,
+    mut actors: Query<(
+        Entity,
+        &Vector2D,
+        &Faction,
+        &TurnState,
+        &FieldOfView,
+        &Inventory,
+        &HitPoints<RogueAttributeType>,
+        &Ai,
+        &mut Memory,
+    )>,
+    items: Query<&Vector2D, With<RogueItemType>>,
+    actors_all: Query<(&Vector2D, &Faction, Option<&FieldOfView>)>,
+    mut act_writer: EventWriter<ActEvent>,
+    mut pick_up_writer: EventWriter<ItemPickUpEvent>,
+    map: Res<RogueMap>,
+    factions: Res<FactionRelations>,
+    scheduler: Res<TurnScheduler>,
+) {
+    // NOTE: one actor acts at a time (the one [`TurnScheduler`] gave the turn to)
+    let (id, pt, faction, ts, fov, inv, hp, ai, mut memory) =
+        match scheduler.current().map(|actor| actors.get_mut(actor)) {
+            Some(Ok(actor)) => actor,
+            _ => return,
+        };
+    if *ts != TurnState::Act {
+        return;
+    }
+    let faction_pt: HashMap<_, _> = actors_all.iter().map(|(p, f, _)| (**p, *f)).collect();
+    let item_pt: HashSet<_> = items.iter().map(|p| **p).collect();
+    // NOTE: who is after whom. pack hunters join in
+    let hunts: Vec<_> = actors_all
+        .iter()
+        .filter_map(|(p, f, fov)| {
+            fov.and_then(|fov| closest_enemy(**p, *f, fov, &faction_pt, &factions))
+                .map(|enemy| (*f, **p, enemy))
+        })
+        .collect();
+    let pathfinder = Pathfinder::new(&map, faction_pt.keys().copied());
+    let is_hostile = |p: IVec2| {
+        faction_pt
+            .get(&p)
+            .map_or(false, |f| factions.is_hostile(*faction, *f))
+    };
+    memory.observe(
+        &fov.tiles_visible,
+        fov.tiles_visible.iter().filter(|p| is_hostile(*p)),
+        fov.tiles_visible.iter().filter(|p| item_pt.contains(p)),
+    );
+    let situation = Situation {
+        pt: **pt,
+        home: ai.home,
+        hp_ratio: hp.percent(),
+        enemy: closest_enemy(**pt, *faction, fov, &faction_pt, &factions),
+        enemy_last_seen: memory.last_enemy(),
+        pack_enemy: hunts
+            .iter()
+            .filter(|(f, _, _)| factions.is_allied(*f, *faction))
+            .map(|(_, mate, enemy)| (*enemy, distance_squared(*mate, **pt)))
+            .min_by_key(|(_, d)| *d),
+        sees_item: fov.tiles_visible.iter().any(|p| item_pt.contains(&p)),
+        is_on_item: item_pt.contains(&**pt),
+        is_inventory_full: inv.is_full(),
+        item_last_seen: memory.last_item(),
+    };
+    let mut best: Option<(f32, Plan)> = None;
+    for (behaviour, weight) in ai.behaviours.iter() {
+        if let Some((score, plan)) = score(behaviour, &situation) {
+            let score = score * weight;
+            if best.map_or(true, |(best_score, _)| score > best_score) {
+                best = Some((score, plan));
+            }
+        }
+    }
+
+    let step = match best.map(|(_, plan)| plan).unwrap_or(Plan::Stay) {
+        Plan::Stay => None,
+        Plan::Wander => {
+            let deltas = [
+                IVec2::new(0, 1),
+                IVec2::new(0, -1),
+                IVec2::new(-1, 0),
+                IVec2::new(1, 0),
+                IVec2::new(0, 0), // stay put - skip turn
+                IVec2::new(0, 0), // stay put - skip turn
+                IVec2::new(0, 0), // stay put - skip turn
+                IVec2::new(0, 0), // stay put - skip turn
+                IVec2::new(0, 0),
+            ];
+            Some(**pt + deltas[rng.gen_range(0..deltas.len())])
+        }
+        Plan::Approach(target) => pathfinder.step_towards(**pt, target),
+        Plan::Retreat => {
+            let enemies: Vec<_> = fov
+                .tiles_visible
+                .iter()
+                .filter(|p| is_hostile(*p))
+                .collect();
+            pathfinder
+                .flow_field(enemies)
+                .flee(|p| pathfinder.cost(p))
+                .step_downhill(**pt)
+        }
+        Plan::PickUp => {
+            pick_up_writer.send(ItemPickUpEvent { picker: id });
+            None
+        }
+        Plan::GoForItems => pathfinder
+            .flow_field(item_pt.iter().copied())
+            .step_downhill(**pt),
+    };
+    let mut delta = step.map_or(IVec2::new(0, 0), |step| step - **pt);
+    // NOTE: neutrals are never bumped into (it would attack them). allies swap places.
+    let is_neutral = |p: IVec2| {
+        faction_pt.get(&p).map_or(false, |f| {
+            factions.relation(*faction, *f) == Relation::Neutral
+        })
+    };
+    if delta != IVec2::new(0, 0) && is_neutral(**pt + delta) {
+        delta = IVec2::new(0, 0);
+    }
+    act_writer.send(ActEvent { id, delta });
+}
+
+/// Sightings fade every turn
+pub fn memory_tick(
+    mut turn_start_reader: EventReader<TurnStartEvent>,
+    mut memories: Query<&mut Memory>,
+) {
+    let turns = turn_start_reader.iter().count();
+    if turns == 0 {
+        return;
+    }
+    for mut memory in memories.iter_mut().filter(|m| !m.is_empty()) {
+        for _ in 0..turns {
+            memory.tick();
+        }
+    }
+}
+
+/// Logs what monsters remember whenever it changes (also inspectable with the world inspector)
+#[cfg(feature = "debug")]
+pub fn memory_debug_log(memories: Query<(&Name, &Vector2D, &Memory), Changed<Memory>>) {
+    for (name, pt, memory) in memories.iter() {
+        bevy::log::debug!(
+            "{} at {:?} remembers enemies {:?} items {:?}",
+            name,
+            **pt,
+            memory.enemies,
+            memory.items
+        );
+    }
+}
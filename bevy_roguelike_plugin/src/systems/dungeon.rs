@@ -0,0 +1,429 @@
+use crate::{components::*, resources::*};
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_inventory::{Equipment, Inventory};
+use bevy_roguelike_combat::*;
+use bevy_roguelike_turns::ActionCosts;
+use map_generator::*;
+use rand::prelude::*;
+use std::marker::PhantomData;
+
+/// Assets needed to generate and spawn a dungeon floor
+#[derive(SystemParam)]
+pub struct FloorAssets<'w, 's> {
+    pub asset_server: Res<'w, AssetServer>,
+    pub map_themes: Res<'w, Assets<MapTheme>>,
+    pub map_gen_presets: Res<'w, Assets<MapGenPreset>>,
+    pub item_templates: Res<'w, Assets<ItemTemplate>>,
+    pub actor_templates: Res<'w, Assets<ActorTemplate>>,
+    pub combat_settings: Res<'w, Assets<CombatSettings>>,
+    pub factions: Res<'w, Assets<Factions>>,
+    pub prefabs: Res<'w, Assets<PrefabAsset>>,
+    #[system_param(ignore)]
+    marker: PhantomData<&'s ()>,
+}
+
+/// Loaded assets ordered by their (stable) handle id.
+/// [`Assets::iter`] order changes from run to run, picking from it would not follow the [`RunSeed`].
+pub fn assets_sorted<T: Asset>(assets: &Assets<T>) -> Vec<&T> {
+    let mut assets: Vec<_> = assets.iter().collect();
+    assets.sort_by_key(|(id, _)| *id);
+    assets.into_iter().map(|(_, it)| it).collect()
+}
+
+impl<'w, 's> FloorAssets<'w, 's> {
+    /// loaded templates in a stable order. only the preset set in `options` is used if any.
+    pub fn templates(&self, options: &MapOptions) -> FloorTemplates {
+        let map_gen_presets = if let Some(path) = options.map_gen_preset.as_ref() {
+            self.map_gen_presets
+                .get(&self.asset_server.load(path.as_str()))
+                .into_iter()
+                .collect()
+        } else {
+            assets_sorted(&self.map_gen_presets)
+        };
+        FloorTemplates {
+            map_gen_presets,
+            prefabs: assets_sorted(&self.prefabs)
+                .into_iter()
+                .map(|it| it.0.clone())
+                .collect(),
+            map_themes: assets_sorted(&self.map_themes),
+            item_templates: assets_sorted(&self.item_templates),
+            actor_templates: assets_sorted(&self.actor_templates),
+            combat_settings: assets_sorted(&self.combat_settings)
+                .first()
+                .copied()
+                .expect("combat settings asset should be loaded"),
+            factions: assets_sorted(&self.factions)
+                .first()
+                .copied()
+                .expect("factions asset should be loaded"),
+        }
+    }
+}
+
+/// Templates a dungeon floor is generated and populated from.
+/// Usually taken from loaded assets ([`FloorAssets::templates`]) but can be built by hand (headless simulation).
+pub struct FloorTemplates<'a> {
+    /// one is picked at random for every floor. random map generator is used if empty.
+    pub map_gen_presets: Vec<&'a MapGenPreset>,
+    pub prefabs: Vec<Prefab>,
+    /// only used when rendering
+    pub map_themes: Vec<&'a MapTheme>,
+    pub item_templates: Vec<&'a ItemTemplate>,
+    pub actor_templates: Vec<&'a ActorTemplate>,
+    pub combat_settings: &'a CombatSettings,
+    pub factions: &'a Factions,
+}
+
+/// Generates a map of the dungeon floor at `depth` with prefab rooms stamped into it
+/// and spawns its tiles, items and monsters.
+/// Stairs down are placed at the exit, stairs up at the player start (except the first floor).
+/// Textures are only inserted with an `asset_server` (headless simulation goes without).
+/// `rng` is the floor rng, seeded from [`RogueRng`] (map generators work with `StdRng`).
+pub fn spawn_floor(
+    cmd: &mut Commands,
+    templates: &FloorTemplates,
+    asset_server: Option<&AssetServer>,
+    options: &MapOptions,
+    factions: &FactionRelations,
+    depth: u32,
+    rng: &mut StdRng,
+) -> (Map, MapInfo, MapEntities) {
+    let map_generator = if let Some(preset) = templates.map_gen_presets.choose(rng) {
+        bevy::log::info!("map generation preset: {}", preset.name);
+        preset.to_pipeline()
+    } else {
+        bevy::log::warn!("map generation preset not found. using random map generator");
+        MapPipeline::new(RandomMapGenerator {})
+    };
+    let mut map = map_generator.gen(rng, options.map_size);
+    let mut prefabs = PrefabStamper {
+        prefabs: templates.prefabs.clone(),
+        count: options.spawn.prefab_count,
+    }
+    .stamp(rng, &mut map);
+    EnsureConnected::default().modify(rng, &mut map);
+    let info = MapInfo::from_map(&map, &options.spawn, depth, &prefabs, rng);
+    let mut info = if let Some(info) = info {
+        info
+    } else {
+        // NOTE: presets are data, a bad one can leave no floor at all
+        bevy::log::warn!("generated map has no floor to start on. using an empty map instead");
+        map = EmptyGenerator {}.gen(rng, options.map_size);
+        prefabs.clear();
+        MapInfo::from_map(&map, &options.spawn, depth, &prefabs, rng).unwrap_or_else(|| {
+            let center = map.size() / 2;
+            map[center] = Tile::Floor;
+            MapInfo::at(center)
+        })
+    };
+    map[info.exit] = Tile::StairsDown;
+    if depth > 1 {
+        map[info.player_start] = Tile::StairsUp;
+    }
+    if options.lighting.enabled {
+        info.torches = WallTorches {
+            spacing: options.lighting.torch_spacing,
+        }
+        .place(rng, &map);
+    }
+
+    #[cfg(feature = "debug")]
+    bevy::log::trace!("{}", map.to_colorized_string());
+    #[cfg(feature = "debug")]
+    bevy::log::info!("{}", info.to_colorized_string());
+
+    // NOTE: cosmetic draws come from a separate rng,
+    // so the same seed plays out the same with or without rendering.
+    let mut cosmetic_rng = StdRng::seed_from_u64(rng.gen());
+    let map_theme = templates.map_themes.choose(&mut cosmetic_rng);
+    let mut tiles = vec![];
+    let map_id = cmd
+        .spawn((
+            SpatialBundle::default(),
+            Name::new(format!("RogueMap {}", depth)),
+        ))
+        .with_children(|rogue_map| {
+            for (pt, tile) in map.enumerate() {
+                let mut ecmd = rogue_map.spawn((
+                    Name::new(format!("Tile {}", pt)),
+                    Vector2D::from(pt),
+                    MapTile::from(*tile),
+                ));
+                if let (Some(asset_server), Some(map_theme)) = (asset_server, map_theme) {
+                    let textures = map_theme.textures(*tile);
+                    ecmd.insert(RenderInfo {
+                        texture: asset_server
+                            .load(textures[cosmetic_rng.gen_range(0..textures.len())].as_str()),
+                        cosmetic_textures: vec![],
+                        z: 0.,
+                    });
+                }
+                tiles.push(ecmd.id());
+            }
+        })
+        .id();
+    cmd.entity(map_id).insert(MapTiles::new(map.size(), tiles));
+
+    let quality_bonus = options.spawn.item_quality_per_depth * depth.saturating_sub(1) as i32;
+    let items_id = cmd
+        .spawn((SpatialBundle::default(), Name::new("Items")))
+        .with_children(|cb| {
+            if templates.item_templates.is_empty() {
+                return;
+            }
+            for ipt in info.item_spawns.clone() {
+                let template =
+                    templates.item_templates[rng.gen_range(0..templates.item_templates.len())];
+                let quality = Quality::roll(rng, quality_bonus);
+                let mut ecmd = cb.spawn(Vector2D::from(ipt));
+                spawn_item(&mut ecmd, template, &quality, rng);
+                if let Some(asset_server) = asset_server {
+                    insert_item_render(&mut ecmd, asset_server, template);
+                }
+            }
+        })
+        .id();
+
+    let mut monster_templates: Vec<_> = templates
+        .actor_templates
+        .iter()
+        .copied()
+        .filter(|it| it.min_depth <= depth)
+        .collect();
+    if monster_templates.is_empty() {
+        bevy::log::warn!("no monster templates for depth {}. using all", depth);
+        monster_templates = templates.actor_templates.clone();
+    }
+    let enemies_id = cmd
+        .spawn((SpatialBundle::default(), Name::new("Enemies")))
+        .with_children(|enms| {
+            if monster_templates.is_empty() {
+                return;
+            }
+            for mpt in info.monster_spawns.clone() {
+                let monster_template = monster_templates[rng.gen_range(0..monster_templates.len())];
+                let mut ecmd = enms.spawn((
+                    Actor::new(
+                        monster_template,
+                        templates.combat_settings,
+                        faction_of(factions, monster_template),
+                        mpt,
+                    ),
+                    Ai::new(&monster_template.behaviours, mpt),
+                    monster_template.memory.clone(),
+                ));
+                if let Some(asset_server) = asset_server {
+                    ecmd.insert(monster_template.render.load(asset_server));
+                }
+            }
+        })
+        .id();
+
+    (
+        map,
+        info,
+        MapEntities {
+            map_id,
+            enemies_id,
+            items_id,
+        },
+    )
+}
+
+/// Seeds the run, spawns the first dungeon floor and the player at its start
+/// and inserts the dungeon resources ([`RogueMap`], [`MapInfo`], [`MapEntities`], [`Dungeon`], [`RogueRng`], [`RunSeed`], [`FactionRelations`]).
+/// Returns the player entity and its position, the player still needs a controller ([`MovingPlayer`] or [`Ai`] with [`Memory`]).
+pub fn spawn_dungeon(
+    cmd: &mut Commands,
+    templates: &FloorTemplates,
+    player_template: &ActorTemplate,
+    asset_server: Option<&AssetServer>,
+    options: &MapOptions,
+    seed: u64,
+) -> (Entity, IVec2) {
+    let mut rng = RogueRng::seed_from_u64(seed);
+    let dungeon = Dungeon::default();
+    let factions = FactionRelations::new(
+        templates.factions,
+        [player_template]
+            .into_iter()
+            .chain(templates.actor_templates.iter().copied())
+            .map(|t| t.faction.as_str()),
+    );
+    let (map, info, map_entities) = spawn_floor(
+        cmd,
+        templates,
+        asset_server,
+        options,
+        &factions,
+        dungeon.depth,
+        &mut StdRng::seed_from_u64(rng.gen()),
+    );
+
+    let mut player = cmd.spawn(Actor::new(
+        player_template,
+        templates.combat_settings,
+        faction_of(&factions, player_template),
+        info.player_start,
+    ));
+    if let Some(asset_server) = asset_server {
+        player.insert(player_template.render.load(asset_server));
+    }
+    let player = player.id();
+    let player_start = info.player_start;
+
+    cmd.insert_resource(RogueMap(map));
+    cmd.insert_resource(info);
+    cmd.insert_resource(rng);
+    cmd.insert_resource(RunSeed(seed));
+    cmd.insert_resource(map_entities);
+    cmd.insert_resource(dungeon);
+    cmd.insert_resource(factions);
+    (player, player_start)
+}
+
+/// faction declared in the template. the first faction if it is unknown.
+fn faction_of(factions: &FactionRelations, template: &ActorTemplate) -> Faction {
+    factions.faction(&template.faction).unwrap_or_else(|| {
+        bevy::log::warn!(
+            "{} faction {} not found",
+            template.render.name,
+            template.faction
+        );
+        Faction::default()
+    })
+}
+
+/// Player standing on stairs moves to the floor above or below.
+/// The current floor is kept out of play and the next one is restored or generated.
+#[allow(clippy::type_complexity, clippy::too_many_arguments)]
+pub fn use_stairs(
+    mut cmd: Commands,
+    keys: Res<Input<KeyCode>>,
+    mut players: Query<
+        (
+            Entity,
+            &mut Vector2D,
+            &mut FieldOfView,
+            &TurnState,
+            &Inventory,
+            &Equipment<RogueItemType>,
+        ),
+        With<MovingPlayer>,
+    >,
+    monsters: Query<(Entity, &Vector2D), (With<Faction>, Without<MovingPlayer>)>,
+    items: Query<(Entity, &Vector2D), (With<RogueItemType>, Without<MovingPlayer>)>,
+    mut visibilities: Query<&mut Visibility>,
+    mut ap_spend_writer: EventWriter<SpendAPEvent>,
+    mut dungeon: ResMut<Dungeon>,
+    mut map: ResMut<RogueMap>,
+    mut info: ResMut<MapInfo>,
+    mut entities: ResMut<MapEntities>,
+    mut rng: ResMut<RogueRng>,
+    options: Res<MapOptions>,
+    factions: Res<FactionRelations>,
+    costs: Res<ActionCosts>,
+    assets: FloorAssets,
+) {
+    if !keys.just_pressed(KeyCode::Period) {
+        return;
+    }
+    if let Ok((player, mut pt, mut fov, ts, inventory, equipment)) = players.get_single_mut() {
+        if *ts != TurnState::Act {
+            return;
+        }
+        let depth = match map[**pt] {
+            Tile::StairsDown => dungeon.depth + 1,
+            Tile::StairsUp if dungeon.depth > 1 => dungeon.depth - 1,
+            _ => return,
+        };
+
+        for root in [entities.map_id, entities.enemies_id, entities.items_id] {
+            if let Ok(mut visibility) = visibilities.get_mut(root) {
+                visibility.is_visible = false;
+            }
+        }
+        let monsters: Vec<_> = monsters.iter().map(|(e, p)| (e, **p)).collect();
+        for (monster, _) in monsters.iter() {
+            cmd.entity(*monster)
+                .remove::<Vector2D>()
+                .remove::<TurnState>();
+        }
+        let items: Vec<_> = items.iter().map(|(e, p)| (e, **p)).collect();
+        for (item, _) in items.iter() {
+            cmd.entity(*item).remove::<Vector2D>();
+        }
+        let mut tiles_revealed = std::mem::take(&mut fov.tiles_revealed);
+        tiles_revealed.union_with(&std::mem::take(&mut fov.tiles_visible));
+        let depth_left = dungeon.depth;
+        dungeon.floors.insert(
+            depth_left,
+            DungeonFloor {
+                map: map.0.clone(),
+                info: info.clone(),
+                entities: *entities,
+                tiles_revealed,
+                monsters,
+                items,
+            },
+        );
+
+        let arrival = if let Some(floor) = dungeon.floors.remove(&depth) {
+            for root in [
+                floor.entities.map_id,
+                floor.entities.enemies_id,
+                floor.entities.items_id,
+            ] {
+                if let Ok(mut visibility) = visibilities.get_mut(root) {
+                    visibility.is_visible = true;
+                }
+            }
+            for (monster, mpt) in floor.monsters {
+                cmd.entity(monster)
+                    .insert(Vector2D::from(mpt))
+                    .insert(TurnState::default());
+            }
+            for (item, ipt) in floor.items {
+                cmd.entity(item).insert(Vector2D::from(ipt));
+            }
+            fov.tiles_revealed = floor.tiles_revealed;
+            *map = RogueMap(floor.map);
+            *info = floor.info;
+            *entities = floor.entities;
+            if depth > depth_left {
+                info.player_start
+            } else {
+                info.exit
+            }
+        } else {
+            let (new_map, new_info, new_entities) = spawn_floor(
+                &mut cmd,
+                &assets.templates(&options),
+                Some(&*assets.asset_server),
+                &options,
+                &factions,
+                depth,
+                &mut StdRng::seed_from_u64(rng.gen()),
+            );
+            *map = RogueMap(new_map);
+            *info = new_info;
+            *entities = new_entities;
+            info.player_start
+        };
+
+        // NOTE: carried items belong to the floor the player is on
+        let carried: Vec<_> = inventory
+            .iter_some()
+            .chain(equipment.iter_some().map(|(_, item)| item))
+            .collect();
+        cmd.entity(entities.items_id).push_children(&carried);
+
+        *pt = Vector2D::from(arrival);
+        fov.is_dirty = true;
+        dungeon.depth = depth;
+        bevy::log::info!("player entered dungeon floor {}", depth);
+        ap_spend_writer.send(SpendAPEvent::new(player, costs.stairs));
+    }
+}
@@ -0,0 +1,28 @@
+use crate::{components::*, resources::FactionRelations};
+use bevy::prelude::*;
+use bevy_roguelike_combat::AttackEvent;
+
+/// Attacking a neutral actor turns its faction hostile to the attacker's (see [`FactionRelations::provokes_neutrals`])
+pub fn faction_provoke(
+    mut attack_reader: EventReader<AttackEvent>,
+    actors: Query<&Faction>,
+    mut factions: ResMut<FactionRelations>,
+) {
+    if !factions.provokes_neutrals() {
+        return;
+    }
+    for attack in attack_reader.iter() {
+        if let (Ok(attacker), Ok(defender)) =
+            (actors.get(attack.attacker), actors.get(attack.defender))
+        {
+            if factions.relation(*attacker, *defender) == Relation::Neutral {
+                factions.set_relation(*attacker, *defender, Relation::Hostile);
+                bevy::log::info!(
+                    "{} provoked {}. now hostile",
+                    factions.name(*attacker),
+                    factions.name(*defender)
+                );
+            }
+        }
+    }
+}
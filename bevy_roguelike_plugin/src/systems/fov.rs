@@ -1,33 +1,79 @@
 use crate::{
     components::*,
-    resources::{MapOptions, RogueMap},
+    resources::{FovCache, MapEntities, MapOptions, RogueLightMap, RogueMap},
 };
-use bevy::{prelude::*, utils::HashSet};
+use bevy::{prelude::*, tasks::ComputeTaskPool};
 use bevy_roguelike_combat::{ActionPoints, HitPoints};
-use line_drawing::{BresenhamCircle, Supercover};
-use map_generator::*;
+use map_generator::BitGrid;
 
+/// Player field of view sprites were last updated for
+#[derive(Default)]
+pub struct VisibilityPropagated {
+    tiles_visible: BitGrid,
+}
+
+/// Updates sprites only on tiles whose visibility changed since the last run
+/// and of entities that moved, got new sprites or lost health.
+/// Everything is updated when the player arrives to a floor, visible tiles when the light changes.
+/// Visible sprites are tinted by the tile light level.
 #[allow(clippy::type_complexity)]
 pub fn field_of_view_set_visibility(
     players: Query<&FieldOfView, With<MovingPlayer>>,
-    mut visibles: Query<(
+    visibles: Query<(
+        Entity,
         &Vector2D,
         &Children,
         Option<&ActionPoints<RogueAttributeType>>,
         Option<&HitPoints<RogueAttributeType>>,
     )>,
+    visibles_changed: Query<
+        Entity,
+        (
+            With<Children>,
+            Or<(
+                Changed<Vector2D>,
+                Changed<Children>,
+                Changed<HitPoints<RogueAttributeType>>,
+            )>,
+        ),
+    >,
     mut visible_children: Query<(
         &mut Sprite,
         &mut Transform,
         &mut Visibility,
         Option<&HudHealthBar>,
     )>,
+    map_entities: Option<Res<MapEntities>>,
     map_options: Res<MapOptions>,
+    light_map: Res<RogueLightMap>,
+    mut propagated: Local<VisibilityPropagated>,
 ) {
-    for fov in players.iter() {
-        visibles.for_each_mut(|(pt, children, cp, hp)| {
-            let is_revealed = fov.tiles_revealed.contains(pt);
-            let is_visible = fov.tiles_visible.contains(pt);
+    let fov = if let Ok(fov) = players.get_single() {
+        fov
+    } else {
+        return;
+    };
+    let is_floor_new = map_entities.map_or(false, |e| e.is_changed());
+    let mut tiles_changed = if fov.tiles_visible != propagated.tiles_visible {
+        let tiles_changed = fov
+            .tiles_visible
+            .symmetric_difference(&propagated.tiles_visible);
+        propagated.tiles_visible.clone_from(&fov.tiles_visible);
+        tiles_changed
+    } else {
+        BitGrid::default()
+    };
+    if light_map.is_changed() {
+        tiles_changed.union_with(&fov.tiles_visible);
+    }
+
+    let mut set_visibility =
+        |pt: &Vector2D,
+         children: &Children,
+         cp: Option<&ActionPoints<RogueAttributeType>>,
+         hp: Option<&HitPoints<RogueAttributeType>>| {
+            let is_revealed = fov.tiles_revealed.contains(**pt);
+            let is_visible = fov.tiles_visible.contains(**pt);
             let is_ambient = cp.is_none();
             let hp_percent = hp.map(|h| h.percent()).unwrap_or(1.);
             for c in children.iter() {
@@ -37,7 +83,7 @@ pub fn field_of_view_set_visibility(
                         (hp_percent != 1. || !is_hud_hp) && is_visible || is_ambient && is_revealed;
                     if !is_hud_hp {
                         s.color = if is_visible && is_revealed {
-                            Color::WHITE
+                            light_tint(light_map.level(**pt))
                         } else {
                             Color::rgb(0.65, 0.65, 0.65)
                         };
@@ -54,40 +100,82 @@ pub fn field_of_view_set_visibility(
                     }
                 }
             }
-        });
+        };
+
+    if is_floor_new || !tiles_changed.is_empty() {
+        for (entity, pt, children, cp, hp) in visibles.iter() {
+            if is_floor_new || tiles_changed.contains(**pt) || visibles_changed.contains(entity) {
+                set_visibility(pt, children, cp, hp);
+            }
+        }
+    } else {
+        for entity in visibles_changed.iter() {
+            if let Ok((_, pt, children, cp, hp)) = visibles.get(entity) {
+                set_visibility(pt, children, cp, hp);
+            }
+        }
     }
 }
 
+/// Sprite color of a visible tile. Unlit tiles are dim and cold, fully lit ones are white.
+fn light_tint(level: f32) -> Color {
+    let level = level.clamp(0., 1.);
+    Color::rgb(0.45 + 0.55 * level, 0.5 + 0.5 * level, 0.7 + 0.3 * level)
+}
+
+/// Recomputes dirty fields of view. Actors sharing origin and radius share the result
+/// (see [`FovCache`]), missing results are computed in parallel.
+/// Tiles too dark to be seen from the actor position are left out (see [`crate::resources::LightingOptions`]).
 pub fn field_of_view_recompute(
     mut actors: Query<(&Vector2D, &mut FieldOfView)>,
+    mut fov_cache: ResMut<FovCache>,
     map: Res<RogueMap>,
+    light_map: Res<RogueLightMap>,
+    map_options: Res<MapOptions>,
 ) {
+    if map.is_changed() || map_options.is_changed() {
+        fov_cache.clear();
+    }
+    let mut missing = vec![];
+    for (pt, fov) in actors.iter().filter(|(_, fov)| fov.is_dirty) {
+        let key = (**pt, fov.radius);
+        if !fov_cache.contains(key.0, key.1) && !missing.contains(&key) {
+            missing.push(key);
+        }
+    }
+    if !missing.is_empty() {
+        let map = &map.0;
+        let options = &map_options.fov;
+        let computed = ComputeTaskPool::get().scope(|scope| {
+            for (origin, radius) in missing {
+                scope.spawn(async move { (origin, radius, options.compute(map, origin, radius)) });
+            }
+        });
+        for (origin, radius, visible) in computed {
+            fov_cache.insert(origin, radius, visible);
+        }
+    }
+
+    let fov_cache = &*fov_cache;
+    let light_map = &*light_map;
+    let lighting = &map_options.lighting;
     actors.par_for_each_mut(16, |(pt, mut fov)| {
         if !fov.is_dirty {
             return;
         }
-        let visible_last = fov.tiles_visible.clone();
-        fov.tiles_visible = compute_fov(**pt, fov.radius, &map);
-        let visible_current = fov.tiles_visible.clone();
-        fov.tiles_revealed.extend(visible_last);
-        fov.tiles_revealed.extend(visible_current);
-        fov.is_dirty = false;
-    });
-}
-
-fn compute_fov(pt: IVec2, radius: i32, map: &Map) -> HashSet<IVec2> {
-    let mut fov = HashSet::default();
-    for (xo, yo) in BresenhamCircle::new(pt.x, pt.y, radius) {
-        for vpt in Supercover::new((pt.x, pt.y), (xo, yo))
-            .map(|(x, y)| IVec2::new(x, y))
-            .filter(|p| map.is_in_bounds(*p))
-        {
-            if map[vpt] == Tile::Wall {
-                fov.insert(vpt);
-                break;
+        if let Some(visible) = fov_cache.get(**pt, fov.radius) {
+            let fov = &mut *fov;
+            fov.tiles_visible.clone_from(visible);
+            if lighting.enabled {
+                for tile in visible
+                    .iter()
+                    .filter(|tile| !lighting.can_see(light_map, **pt, *tile))
+                {
+                    fov.tiles_visible.remove(tile);
+                }
             }
-            fov.insert(vpt);
+            fov.tiles_revealed.union_with(&fov.tiles_visible);
         }
-    }
-    fov
+        fov.is_dirty = false;
+    });
 }
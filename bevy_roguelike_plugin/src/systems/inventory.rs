@@ -28,7 +28,7 @@ pub fn item_fill_text_info<I: ItemType>(
         for (entity, name, quality, attributes, protection, resistance, damage, block, pt, info) in
             items.iter()
         {
-            if player_fov.tiles_visible.iter().any(|t| *t == **pt) {
+            if player_fov.tiles_visible.contains(**pt) {
                 let name = name.as_str().to_string()
                     + match quality {
                         Quality::Broken => " (broken)",
@@ -83,19 +83,12 @@ pub fn item_fill_text_info<I: ItemType>(
     }
 }
 
-#[allow(clippy::type_complexity)]
+/// Items without render info (headless simulation) have no rendered children.
 pub fn pick_up_items<I: ItemType>(
     mut cmd: Commands,
     mut pick_up_item_reader: EventReader<ItemPickUpEvent>,
     mut actors: Query<(&Vector2D, &mut Inventory, &mut Equipment<I>)>,
-    items: Query<
-        (Entity, &Vector2D, &I, &Children),
-        (
-            With<Transform>,
-            With<GlobalTransform>,
-            With<Visibility>,
-        ),
-    >,
+    items: Query<(Entity, &Vector2D, &I, Option<&Children>)>,
 ) {
     for e in pick_up_item_reader.iter() {
         if let Ok((actor_pt, mut inventory, mut equipment)) = actors.get_mut(e.picker) {
@@ -103,7 +96,7 @@ pub fn pick_up_items<I: ItemType>(
                 items.iter().filter(|(_, pt, _, _)| **pt == *actor_pt)
             {
                 if equipment.add(item_entity, item_type) || inventory.add(item_entity) {
-                    for c in children.iter() {
+                    for c in children.into_iter().flat_map(|c| c.iter()) {
                         cmd.entity(*c).despawn_recursive();
                     }
                     cmd.entity(item_entity)
@@ -0,0 +1,98 @@
+use crate::{components::*, events::TurnStartEvent, resources::*};
+use bevy::prelude::*;
+use bevy_roguelike_combat::HitEvent;
+use map_generator::LightMap;
+
+/// Recomputes tile light levels when light sources (or the map) change.
+/// Every field of view is marked dirty since sight depends on light.
+#[allow(clippy::too_many_arguments)]
+pub fn light_map_recompute(
+    lights: Query<(&Vector2D, &LightSource)>,
+    lights_equiped: Query<(&ItemEquipedOwned, &LightSource)>,
+    owners: Query<&Vector2D>,
+    mut fovs: Query<&mut FieldOfView>,
+    mut light_map: ResMut<RogueLightMap>,
+    map: Res<RogueMap>,
+    info: Res<MapInfo>,
+    map_options: Res<MapOptions>,
+    mut lights_last: Local<Vec<(IVec2, LightSource)>>,
+) {
+    let lighting = &map_options.lighting;
+    let mut lights_now = vec![];
+    if lighting.enabled {
+        lights_now.extend(info.torches.iter().map(|pt| (*pt, lighting.torch)));
+        lights_now.extend(lights.iter().map(|(pt, light)| (**pt, *light)));
+        // NOTE: owners on other floors have no position so their items do not light this one
+        lights_now.extend(
+            lights_equiped
+                .iter()
+                .filter_map(|(owned, light)| owners.get(owned.actor).ok().map(|pt| (**pt, *light))),
+        );
+    }
+    if !map.is_changed()
+        && !info.is_changed()
+        && !map_options.is_changed()
+        && lights_now == *lights_last
+    {
+        return;
+    }
+
+    let ambient = if lighting.enabled {
+        lighting.ambient
+    } else {
+        1.
+    };
+    let mut levels = LightMap::new(map.size(), ambient);
+    for (pt, light) in lights_now.iter() {
+        levels.add(&map, &map_options.fov, *pt, (*light).into());
+    }
+    light_map.0 = levels;
+    *lights_last = lights_now;
+    fovs.for_each_mut(|mut fov| fov.is_dirty = true);
+}
+
+/// Fire damage hits light up the defender tile for a few turns
+pub fn light_fire_hits(
+    mut cmd: Commands,
+    mut hit_reader: EventReader<HitEvent<RogueDamageKind>>,
+    actors: Query<&Vector2D>,
+    map_options: Res<MapOptions>,
+) {
+    let lighting = &map_options.lighting;
+    for e in hit_reader
+        .iter()
+        .filter(|e| e.kind == RogueDamageKind::Fire)
+    {
+        if let Ok(pt) = actors.get(e.defender) {
+            cmd.spawn((
+                Name::new("Fire flash"),
+                Vector2D::from(**pt),
+                lighting.fire_flash,
+                LightFlash {
+                    turns_left: lighting.fire_flash_turns,
+                },
+            ));
+        }
+    }
+}
+
+/// Flashes go out when their turns run out or when the player leaves the floor
+pub fn light_flash_fade(
+    mut cmd: Commands,
+    mut flashes: Query<(Entity, &mut LightFlash)>,
+    mut turn_start_reader: EventReader<TurnStartEvent>,
+    map_entities: Option<Res<MapEntities>>,
+) {
+    let is_floor_new = map_entities.map_or(false, |e| e.is_changed());
+    let turns = turn_start_reader.iter().count() as u32;
+    if turns == 0 && !is_floor_new {
+        return;
+    }
+    for (entity, mut flash) in flashes.iter_mut() {
+        if is_floor_new || flash.turns_left <= turns {
+            cmd.entity(entity).despawn();
+        } else {
+            flash.turns_left -= turns;
+        }
+    }
+}
@@ -1,9 +1,15 @@
 pub mod action;
 pub mod actor_stats;
+pub mod ai;
 pub mod camera;
+pub mod dungeon;
+pub mod faction;
 pub mod fov;
 pub mod input;
 pub mod inventory;
+pub mod light;
 pub mod map;
+pub mod pathfinding;
 pub mod render;
+pub mod save;
 pub mod turns;
@@ -0,0 +1,64 @@
+use bevy::prelude::IVec2;
+use map_generator::*;
+
+/// Extra cost of entering a tile with an actor on it.
+/// Actors move around, so they are only avoided when the way around is short.
+const OCCUPIED_COST: i32 = 4;
+
+/// Path queries over the current floor ([`crate::resources::RogueMap`]).
+/// Impassable tiles are never entered, tiles hurting on enter (lava) cost extra
+/// and tiles occupied by actors are soft obstacles.
+/// Actors move up, down, left and right, so do paths.
+pub struct Pathfinder<'a> {
+    map: &'a Map,
+    occupied: BitGrid,
+}
+
+impl<'a> Pathfinder<'a> {
+    pub fn new(map: &'a Map, occupied: impl IntoIterator<Item = IVec2>) -> Self {
+        let mut grid = BitGrid::new(map.size());
+        grid.extend(occupied);
+        Self {
+            map,
+            occupied: grid,
+        }
+    }
+
+    /// cost of entering the tile. `None` if impassable.
+    pub fn cost(&self, pt: IVec2) -> Option<i32> {
+        if !self.map.is_in_bounds(pt) {
+            return None;
+        }
+        let tile = self.map[pt];
+        let occupied_cost = if self.occupied.contains(pt) {
+            OCCUPIED_COST
+        } else {
+            0
+        };
+        tile.movement_cost()
+            .map(|cost| cost + tile.damage_on_enter() as i32 + occupied_cost)
+    }
+
+    /// cheapest path from `start` to `goal` (both included) using A*
+    pub fn path(&self, start: IVec2, goal: IVec2) -> Option<Vec<IVec2>> {
+        a_star(self.map.size(), start, goal, Neighborhood::Four, |pt| {
+            self.cost(pt)
+        })
+    }
+
+    /// next tile on the cheapest path to `goal`. `None` if unreachable or already there.
+    pub fn step_towards(&self, start: IVec2, goal: IVec2) -> Option<IVec2> {
+        self.path(start, goal).and_then(|path| path.get(1).copied())
+    }
+
+    /// Flow field (distance to the closest of `sources` from every tile).
+    /// Shared by any number of actors, each rolls downhill ([`DijkstraMap::step_downhill`]) to approach.
+    pub fn flow_field(&self, sources: impl IntoIterator<Item = IVec2>) -> DijkstraMap {
+        DijkstraMap::with_cost(
+            self.map.size(),
+            sources.into_iter().map(|pt| (pt, 0)),
+            Neighborhood::Four,
+            |pt| self.cost(pt),
+        )
+    }
+}
@@ -1,4 +1,7 @@
-use crate::{components::*, resources::MapOptions};
+use crate::{
+    components::*,
+    resources::{Dungeon, MapOptions, RunSeed},
+};
 use bevy::prelude::*;
 use bevy_inventory::{Equipment, ItemType};
 use bevy_roguelike_combat::HitPoints;
@@ -136,3 +139,18 @@ pub fn render_hud_health_bar(
             });
     }
 }
+
+pub fn render_hud_run_info(
+    mut texts: Query<&mut Text, With<HudRunInfo>>,
+    seed: Res<RunSeed>,
+    dungeon: Res<Dungeon>,
+) {
+    if !dungeon.is_changed() && !seed.is_changed() {
+        return;
+    }
+    for mut text in texts.iter_mut() {
+        if let Some(section) = text.sections.first_mut() {
+            section.value = format!("seed {} depth {}", seed.0, dungeon.depth);
+        }
+    }
+}
@@ -0,0 +1,652 @@
+use crate::{components::*, resources::*};
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use bevy_inventory::{Equipment, Inventory};
+use bevy_inventory_ui::{EquipmentDisplay, InventoryDisplayOwner, UiRenderInfo};
+use bevy_roguelike_combat::*;
+use bevy_roguelike_turns::TurnScheduler;
+use map_generator::BitGrid;
+
+/// Save file written with `F5` and read with `F9`
+pub const SAVE_PATH: &str = "rogue.save.ron";
+
+type ControllerQuery = (
+    Option<&'static MovingPlayer>,
+    Option<&'static Ai>,
+    Option<&'static Memory>,
+);
+type CombatQuery = (
+    &'static Attributes<RogueAttributeType>,
+    &'static ActionPoints<RogueAttributeType>,
+    &'static HitPoints<RogueAttributeType>,
+    &'static DamageList<RogueDamageKind, RogueAttributeType>,
+    &'static Protection<RogueDamageKind, RogueAttributeType>,
+    &'static Evasion<RogueAttributeType>,
+    &'static Resistance<RogueDamageKind>,
+    &'static StatsComputed<RogueDamageKind, RogueAttributeType>,
+);
+type ActorQuery = (
+    Entity,
+    &'static Name,
+    &'static Faction,
+    Option<&'static TurnState>,
+    Option<&'static Vector2D>,
+    Option<&'static Parent>,
+    &'static FieldOfView,
+    &'static EquipmentDisplay<RogueItemType>,
+    &'static Equipment<RogueItemType>,
+    &'static Inventory,
+    Option<&'static RenderInfo>,
+    ControllerQuery,
+    CombatQuery,
+);
+type ItemStatsQuery = (
+    Option<&'static Attributes<RogueAttributeType>>,
+    Option<&'static Protection<RogueDamageKind, RogueAttributeType>>,
+    Option<&'static Resistance<RogueDamageKind>>,
+    Option<&'static Block<RogueDamageKind, RogueAttributeType>>,
+    Option<&'static Damage<RogueDamageKind, RogueAttributeType>>,
+);
+type ItemQuery = (
+    Entity,
+    &'static Name,
+    &'static RogueItemType,
+    &'static Quality,
+    Option<&'static Vector2D>,
+    Option<&'static Parent>,
+    Option<&'static RenderInfo>,
+    Option<&'static RenderInfoEquiped>,
+    Option<&'static LightSource>,
+    ItemStatsQuery,
+);
+
+/// Running game state a [`SaveGame`] is captured from
+#[derive(SystemParam)]
+pub struct SaveSource<'w, 's> {
+    actors: Query<'w, 's, ActorQuery>,
+    items: Query<'w, 's, ItemQuery>,
+    tiles: Query<'w, 's, (&'static Vector2D, &'static RenderInfo), With<MapTile>>,
+    children: Query<'w, 's, &'static Children>,
+    map: Res<'w, RogueMap>,
+    info: Res<'w, MapInfo>,
+    entities: Res<'w, MapEntities>,
+    dungeon: Res<'w, Dungeon>,
+    seed: Res<'w, RunSeed>,
+    factions: Res<'w, FactionRelations>,
+    rng: Res<'w, RogueRng>,
+    scheduler: Res<'w, TurnScheduler>,
+}
+
+/// Floor being saved: its root entities and positions of monsters and items taken out of play
+struct FloorSource<'a> {
+    depth: u32,
+    map: &'a map_generator::Map,
+    info: &'a MapInfo,
+    entities: MapEntities,
+    tiles_revealed: Vec<IVec2>,
+    positions: HashMap<Entity, IVec2>,
+}
+
+impl<'w, 's> SaveSource<'w, 's> {
+    /// Captures the whole dungeon, the player (the actor without a parent) and every item.
+    /// Textures are saved only with an `asset_server` (headless simulation goes without).
+    pub fn capture(&self, asset_server: Option<&AssetServer>) -> Option<SaveGame> {
+        let mut floors = vec![FloorSource {
+            depth: self.dungeon.depth,
+            map: &self.map,
+            info: &self.info,
+            entities: *self.entities,
+            tiles_revealed: vec![],
+            positions: HashMap::default(),
+        }];
+        let mut away: Vec<_> = self.dungeon.floors.iter().collect();
+        away.sort_by_key(|(depth, _)| **depth);
+        for (depth, floor) in away {
+            floors.push(FloorSource {
+                depth: *depth,
+                map: &floor.map,
+                info: &floor.info,
+                entities: floor.entities,
+                tiles_revealed: floor.tiles_revealed.iter().collect(),
+                positions: floor
+                    .monsters
+                    .iter()
+                    .chain(floor.items.iter())
+                    .copied()
+                    .collect(),
+            });
+        }
+        let floor_of = |parent: Option<&Parent>, root: fn(&MapEntities) -> Entity| {
+            parent.and_then(|p| floors.iter().position(|f| root(&f.entities) == p.get()))
+        };
+
+        // NOTE: query order is kept, so systems iterating them see the same order after a load
+        let mut items = vec![];
+        let mut item_ids = HashMap::default();
+        let mut floor_items = vec![vec![]; floors.len()];
+        for (entity, name, item_type, quality, pt, parent, render, render_equiped, light, stats) in
+            self.items.iter()
+        {
+            let floor = floor_of(parent, |e| e.items_id);
+            let position = pt
+                .map(|pt| **pt)
+                .or_else(|| floor.and_then(|f| floors[f].positions.get(&entity).copied()));
+            let (attributes, protection, resistance, block, damage) = stats;
+            item_ids.insert(entity, items.len());
+            if let Some(f) = floor {
+                floor_items[f].push(items.len());
+            }
+            items.push(SavedItem {
+                name: name.to_string(),
+                item_type: *item_type,
+                quality: quality.clone(),
+                position,
+                attributes: attributes.cloned(),
+                protection: protection.cloned(),
+                resistance: resistance.cloned(),
+                block: block.cloned(),
+                damage: damage.cloned(),
+                render: asset_server.and_then(|a| {
+                    render.and_then(|r| save_render(a, &r.texture, &r.cosmetic_textures, r.z))
+                }),
+                render_equiped: asset_server.and_then(|a| {
+                    render_equiped.and_then(|r| save_render(a, &r.texture, &[], r.z))
+                }),
+                light: light.copied(),
+            });
+        }
+
+        let mut player = None;
+        let mut floor_monsters = vec![vec![]; floors.len()];
+        for (
+            entity,
+            name,
+            faction,
+            turn_state,
+            pt,
+            parent,
+            fov,
+            equipment_display,
+            equipment,
+            inventory,
+            render,
+            (moving_player, ai, memory),
+            (attributes, ap, hp, damage, protection, evasion, resistance, stats),
+        ) in self.actors.iter()
+        {
+            let floor = floor_of(parent, |e| e.enemies_id);
+            let position = pt
+                .map(|pt| **pt)
+                .or_else(|| floor.and_then(|f| floors[f].positions.get(&entity).copied()));
+            let position = if let Some(position) = position {
+                position
+            } else {
+                bevy::log::warn!("{} (id: {:?}) has no position. not saved", name, entity);
+                continue;
+            };
+            let controller = if moving_player.is_some() {
+                SavedController::Player
+            } else if let Some(ai) = ai {
+                SavedController::Ai(ai.clone())
+            } else {
+                SavedController::None
+            };
+            let saved = SavedActor {
+                name: name.to_string(),
+                faction: *faction,
+                controller,
+                memory: memory.cloned(),
+                position,
+                turn_state: turn_state.copied().unwrap_or_default(),
+                turn_order: self.scheduler.order_of(entity),
+                attributes: attributes.clone(),
+                action_points: ap.clone(),
+                hit_points: hp.clone(),
+                damage: damage.clone(),
+                protection: protection.clone(),
+                evasion: evasion.clone(),
+                resistance: resistance.clone(),
+                stats: stats.clone(),
+                field_of_view: fov.clone(),
+                equipment_display: equipment_display.clone(),
+                equipment: equipment
+                    .items
+                    .iter()
+                    .map(|(slot, item)| (*slot, item.and_then(|e| item_ids.get(&e).copied())))
+                    .collect(),
+                inventory: inventory
+                    .slots()
+                    .iter()
+                    .map(|item| item.and_then(|e| item_ids.get(&e).copied()))
+                    .collect(),
+                render: asset_server.and_then(|a| {
+                    render.and_then(|r| save_render(a, &r.texture, &r.cosmetic_textures, r.z))
+                }),
+            };
+            match (parent, floor) {
+                (None, _) => player = Some(saved),
+                (Some(_), Some(f)) => floor_monsters[f].push(saved),
+                (Some(_), None) => {
+                    bevy::log::warn!("{} (id: {:?}) is not on any floor. not saved", name, entity)
+                }
+            }
+        }
+        let player = if let Some(player) = player {
+            player
+        } else {
+            bevy::log::error!("player not found. nothing to save");
+            return None;
+        };
+
+        let floors = floors
+            .into_iter()
+            .zip(floor_monsters.into_iter().zip(floor_items))
+            .map(|(floor, (monsters, items_on_floor))| SavedFloor {
+                depth: floor.depth,
+                map: floor.map.clone(),
+                info: floor.info.clone(),
+                tile_textures: asset_server
+                    .map(|a| self.tile_textures(a, floor.map, floor.entities.map_id))
+                    .unwrap_or_default(),
+                tiles_revealed: floor.tiles_revealed,
+                monsters,
+                items: items_on_floor,
+            })
+            .collect();
+
+        Some(SaveGame {
+            seed: self.seed.0,
+            rng: self.rng.clone(),
+            depth: self.dungeon.depth,
+            turn: self.scheduler.turn(),
+            floors,
+            player,
+            items,
+            factions: self.factions.clone(),
+        })
+    }
+
+    /// texture paths of the floor tiles, empty if some tile is not textured
+    fn tile_textures(
+        &self,
+        asset_server: &AssetServer,
+        map: &map_generator::Map,
+        map_id: Entity,
+    ) -> Vec<String> {
+        let textures: HashMap<_, _> = self
+            .children
+            .get(map_id)
+            .into_iter()
+            .flat_map(|c| c.iter())
+            .filter_map(|c| self.tiles.get(*c).ok())
+            .filter_map(|(pt, r)| Some((**pt, asset_path(asset_server, &r.texture)?)))
+            .collect();
+        map.enumerate()
+            .map(|(pt, _)| textures.get(&pt).cloned())
+            .collect::<Option<_>>()
+            .unwrap_or_default()
+    }
+}
+
+fn asset_path(asset_server: &AssetServer, handle: &Handle<Image>) -> Option<String> {
+    asset_server
+        .get_handle_path(handle)
+        .map(|path| path.path().to_string_lossy().into_owned())
+}
+
+fn save_render(
+    asset_server: &AssetServer,
+    texture: &Handle<Image>,
+    cosmetic_textures: &[Handle<Image>],
+    z: f32,
+) -> Option<SavedRender> {
+    Some(SavedRender {
+        texture: asset_path(asset_server, texture)?,
+        cosmetic_textures: cosmetic_textures
+            .iter()
+            .filter_map(|t| asset_path(asset_server, t))
+            .collect(),
+        z,
+    })
+}
+
+fn load_render(asset_server: &AssetServer, render: &SavedRender) -> RenderInfo {
+    RenderInfo {
+        texture: asset_server.load(render.texture.as_str()),
+        cosmetic_textures: render
+            .cosmetic_textures
+            .iter()
+            .map(|t| asset_server.load(t.as_str()))
+            .collect(),
+        z: render.z,
+    }
+}
+
+/// Spawns the saved dungeon, items and player and inserts the dungeon resources
+/// ([`RogueMap`], [`MapInfo`], [`MapEntities`], [`Dungeon`], [`RogueRng`], [`RunSeed`], [`FactionRelations`])
+/// and the [`TurnScheduler`] with actors back in their turn order.
+/// Returns the player entity. Textures are only inserted with an `asset_server`.
+pub fn spawn_save_game(
+    cmd: &mut Commands,
+    save: &SaveGame,
+    asset_server: Option<&AssetServer>,
+) -> Entity {
+    let items: Vec<_> = save
+        .items
+        .iter()
+        .map(|item| spawn_saved_item(cmd, item, asset_server))
+        .collect();
+
+    let mut dungeon = Dungeon {
+        depth: save.depth,
+        floors: HashMap::default(),
+    };
+    let mut scheduler = TurnScheduler::with_turn(save.turn);
+    let mut current = None;
+    for floor in save.floors.iter() {
+        let is_current = floor.depth == save.depth;
+        let spatial = || SpatialBundle {
+            visibility: Visibility {
+                is_visible: is_current,
+            },
+            ..default()
+        };
+        let mut tiles = vec![];
+        let map_id = cmd
+            .spawn((spatial(), Name::new(format!("RogueMap {}", floor.depth))))
+            .with_children(|rogue_map| {
+                for (i, (pt, tile)) in floor.map.enumerate().enumerate() {
+                    let mut ecmd = rogue_map.spawn((
+                        Name::new(format!("Tile {}", pt)),
+                        Vector2D::from(pt),
+                        MapTile::from(*tile),
+                    ));
+                    if let (Some(asset_server), Some(texture)) =
+                        (asset_server, floor.tile_textures.get(i))
+                    {
+                        ecmd.insert(RenderInfo {
+                            texture: asset_server.load(texture.as_str()),
+                            cosmetic_textures: vec![],
+                            z: 0.,
+                        });
+                    }
+                    tiles.push(ecmd.id());
+                }
+            })
+            .id();
+        cmd.entity(map_id)
+            .insert(MapTiles::new(floor.map.size(), tiles));
+
+        let floor_items: Vec<_> = floor
+            .items
+            .iter()
+            .filter_map(|i| Some((items.get(*i).copied()?, save.items[*i].position)))
+            .collect();
+        let items_id = cmd
+            .spawn((spatial(), Name::new("Items")))
+            .push_children(&floor_items.iter().map(|(e, _)| *e).collect::<Vec<_>>())
+            .id();
+        let mut item_positions = vec![];
+        for (entity, position) in floor_items {
+            if let Some(pt) = position {
+                if is_current {
+                    cmd.entity(entity).insert(Vector2D::from(pt));
+                } else {
+                    item_positions.push((entity, pt));
+                }
+            }
+        }
+
+        let mut monsters = vec![];
+        for monster in floor.monsters.iter() {
+            let entity = spawn_saved_actor(cmd, monster, &items, asset_server);
+            let turn_state = if is_current {
+                cmd.entity(entity)
+                    .insert((Vector2D::from(monster.position), monster.turn_state));
+                monster.turn_state
+            } else {
+                // NOTE: monsters of other floors keep only their order, they gather again when back
+                TurnState::Collect
+            };
+            scheduler.restore(
+                entity,
+                monster.turn_order,
+                turn_state,
+                monster.action_points.current(),
+            );
+            monsters.push((entity, monster.position));
+        }
+        let enemies_id = cmd
+            .spawn((spatial(), Name::new("Enemies")))
+            .push_children(&monsters.iter().map(|(e, _)| *e).collect::<Vec<_>>())
+            .id();
+
+        let entities = MapEntities {
+            map_id,
+            enemies_id,
+            items_id,
+        };
+        if is_current {
+            current = Some((floor.map.clone(), floor.info.clone(), entities));
+        } else {
+            dungeon.floors.insert(
+                floor.depth,
+                DungeonFloor {
+                    map: floor.map.clone(),
+                    info: floor.info.clone(),
+                    entities,
+                    tiles_revealed: {
+                        let mut tiles_revealed = BitGrid::new(floor.map.size());
+                        tiles_revealed.extend(floor.tiles_revealed.iter().copied());
+                        tiles_revealed
+                    },
+                    monsters,
+                    items: item_positions,
+                },
+            );
+        }
+    }
+
+    let player = spawn_saved_actor(cmd, &save.player, &items, asset_server);
+    cmd.entity(player)
+        .insert((Vector2D::from(save.player.position), save.player.turn_state));
+    scheduler.restore(
+        player,
+        save.player.turn_order,
+        save.player.turn_state,
+        save.player.action_points.current(),
+    );
+
+    if let Some((map, info, entities)) = current {
+        cmd.insert_resource(RogueMap(map));
+        cmd.insert_resource(info);
+        cmd.insert_resource(entities);
+    } else {
+        bevy::log::error!("saved game has no floor at depth {}", save.depth);
+    }
+    cmd.insert_resource(dungeon);
+    cmd.insert_resource(scheduler);
+    cmd.insert_resource(save.rng.clone());
+    cmd.insert_resource(RunSeed(save.seed));
+    cmd.insert_resource(save.factions.clone());
+    player
+}
+
+fn spawn_saved_item(
+    cmd: &mut Commands,
+    item: &SavedItem,
+    asset_server: Option<&AssetServer>,
+) -> Entity {
+    let mut ecmd = cmd.spawn((
+        Name::new(item.name.clone()),
+        item.item_type,
+        item.quality.clone(),
+    ));
+    if let Some(attributes) = item.attributes.clone() {
+        ecmd.insert(attributes);
+    }
+    if let Some(protection) = item.protection.clone() {
+        ecmd.insert(protection);
+    }
+    if let Some(resistance) = item.resistance.clone() {
+        ecmd.insert(resistance);
+    }
+    if let Some(block) = item.block.clone() {
+        ecmd.insert(block);
+    }
+    if let Some(damage) = item.damage.clone() {
+        ecmd.insert(damage);
+    }
+    if let Some(light) = item.light {
+        ecmd.insert(light);
+    }
+    if let Some(asset_server) = asset_server {
+        if let Some(render) = item.render.as_ref() {
+            let render = load_render(asset_server, render);
+            ecmd.insert((
+                UiRenderInfo {
+                    image: render.texture.clone().into(),
+                },
+                render,
+            ));
+        }
+        if let Some(render) = item.render_equiped.as_ref() {
+            ecmd.insert(RenderInfoEquiped {
+                texture: asset_server.load(render.texture.as_str()),
+                z: render.z,
+            });
+        }
+    }
+    ecmd.id()
+}
+
+/// Spawns the actor without a position and turn state (taken out of play like monsters of other floors).
+/// Equiped items get their owner so stats are not recomputed.
+fn spawn_saved_actor(
+    cmd: &mut Commands,
+    actor: &SavedActor,
+    items: &[Entity],
+    asset_server: Option<&AssetServer>,
+) -> Entity {
+    let item = |i: &Option<usize>| i.and_then(|i| items.get(i).copied());
+    let equipment = Equipment::<RogueItemType> {
+        items: actor
+            .equipment
+            .iter()
+            .map(|(slot, i)| (*slot, item(i)))
+            .collect(),
+    };
+    let equiped: Vec<_> = equipment.iter_some().map(|(_, e)| e).collect();
+    let mut ecmd = cmd.spawn((
+        (
+            Name::new(actor.name.clone()),
+            actor.faction,
+            actor.field_of_view.clone(),
+            actor.equipment_display.clone(),
+            equipment,
+            Inventory::from_slots(actor.inventory.iter().map(item).collect()),
+        ),
+        (
+            actor.attributes.clone(),
+            actor.action_points.clone(),
+            actor.hit_points.clone(),
+            actor.damage.clone(),
+            actor.protection.clone(),
+            actor.evasion.clone(),
+            actor.resistance.clone(),
+            actor.stats.clone(),
+        ),
+    ));
+    match &actor.controller {
+        SavedController::None => {}
+        SavedController::Player => {
+            ecmd.insert(MovingPlayer {});
+        }
+        SavedController::Ai(ai) => {
+            ecmd.insert(ai.clone());
+        }
+    }
+    if let Some(memory) = actor.memory.as_ref() {
+        ecmd.insert(memory.clone());
+    }
+    if let (Some(asset_server), Some(render)) = (asset_server, actor.render.as_ref()) {
+        ecmd.insert(load_render(asset_server, render));
+    }
+    let actor_id = ecmd.id();
+    for item in equiped {
+        cmd.entity(item)
+            .insert(ItemEquipedOwned { actor: actor_id });
+    }
+    actor_id
+}
+
+/// `F5` saves the game to [`SAVE_PATH`] while the player is about to act
+pub fn save_game(
+    keys: Res<Input<KeyCode>>,
+    players: Query<&TurnState, With<MovingPlayer>>,
+    source: SaveSource,
+    asset_server: Res<AssetServer>,
+) {
+    if !keys.just_pressed(KeyCode::F5) || players.iter().all(|ts| *ts != TurnState::Act) {
+        return;
+    }
+    let save = if let Some(save) = source.capture(Some(&asset_server)) {
+        save
+    } else {
+        return;
+    };
+    let text = match ron::ser::to_string_pretty(&save, ron::ser::PrettyConfig::default()) {
+        Ok(text) => text,
+        Err(err) => return bevy::log::error!("could not serialize the game. {}", err),
+    };
+    match std::fs::write(SAVE_PATH, text) {
+        Ok(_) => bevy::log::info!("game saved to {}", SAVE_PATH),
+        Err(err) => bevy::log::error!("could not write {}. {}", SAVE_PATH, err),
+    }
+}
+
+/// `F9` replaces the running game with the one saved in [`SAVE_PATH`]
+#[allow(clippy::type_complexity)]
+pub fn load_game(
+    mut cmd: Commands,
+    keys: Res<Input<KeyCode>>,
+    entities: Res<MapEntities>,
+    dungeon: Res<Dungeon>,
+    players: Query<Entity, (With<Faction>, Without<Parent>)>,
+    inventory_displays: Query<Entity, With<InventoryDisplayOwner>>,
+    asset_server: Res<AssetServer>,
+) {
+    if !keys.just_pressed(KeyCode::F9) {
+        return;
+    }
+    let save: SaveGame = match std::fs::read_to_string(SAVE_PATH)
+        .map_err(|err| err.to_string())
+        .and_then(|text| ron::from_str::<SaveGame>(&text).map_err(|err| err.to_string()))
+        .and_then(|save| {
+            // NOTE: checked before anything is despawned, so a broken save keeps the running game
+            if save.floors.iter().any(|f| f.depth == save.depth) {
+                Ok(save)
+            } else {
+                Err(format!("saved game has no floor at depth {}", save.depth))
+            }
+        }) {
+        Ok(save) => save,
+        Err(err) => return bevy::log::error!("could not load {}. {}", SAVE_PATH, err),
+    };
+
+    for floor_entities in dungeon
+        .floors
+        .values()
+        .map(|f| &f.entities)
+        .chain([&*entities])
+    {
+        cmd.entity(floor_entities.map_id).despawn_recursive();
+        cmd.entity(floor_entities.enemies_id).despawn_recursive();
+        cmd.entity(floor_entities.items_id).despawn_recursive();
+    }
+    for entity in players.iter().chain(inventory_displays.iter()) {
+        cmd.entity(entity).despawn_recursive();
+    }
+
+    spawn_save_game(&mut cmd, &save, Some(&asset_server));
+    bevy::log::info!("game loaded from {} (depth {})", SAVE_PATH, save.depth);
+}
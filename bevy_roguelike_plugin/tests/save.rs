@@ -0,0 +1,61 @@
+//! Saving a headless game and loading it back gives the same game.
+//!
+//! `cargo test -p bevy_roguelike_plugin --test save`
+use bevy::ecs::system::{CommandQueue, SystemState};
+use bevy::prelude::*;
+use bevy_roguelike_combat::RogueRng;
+use bevy_roguelike_plugin::headless::*;
+use bevy_roguelike_plugin::resources::*;
+use bevy_roguelike_plugin::systems::save::*;
+use rand::RngCore;
+
+const SEED: u64 = 42;
+/// updates played before saving, so actors have moved, fought and spent action points
+const UPDATES: usize = 30;
+
+fn with_commands<R>(app: &mut App, f: impl FnOnce(&mut Commands) -> R) -> R {
+    let mut queue = CommandQueue::default();
+    let result = f(&mut Commands::new(&mut queue, &app.world));
+    queue.apply(&mut app.world);
+    result
+}
+
+fn capture(app: &mut App) -> SaveGame {
+    let mut source = SystemState::<SaveSource>::new(&mut app.world);
+    let save = source.get_mut(&mut app.world).capture(None);
+    save.expect("player should be saved")
+}
+
+fn to_ron(save: &SaveGame) -> String {
+    ron::ser::to_string_pretty(save, ron::ser::PrettyConfig::default()).expect("save serializes")
+}
+
+/// headless game played for a few updates, the player fights with the monster AI
+fn played_game() -> App {
+    let templates = HeadlessTemplates::load();
+    let mut app = headless_app(SEED);
+    let options = app.world.resource::<MapOptions>().clone();
+    with_commands(&mut app, |cmd| templates.spawn_dungeon(cmd, &options, SEED));
+    for _ in 0..UPDATES {
+        app.update();
+    }
+    app
+}
+
+#[test]
+fn save_load_round_trip() {
+    let mut played = played_game();
+    let saved = capture(&mut played);
+    let text = to_ron(&saved);
+
+    let loaded: SaveGame = ron::from_str(&text).expect("save deserializes");
+    let mut app = headless_app(SEED);
+    with_commands(&mut app, |cmd| spawn_save_game(cmd, &loaded, None));
+    let resaved = capture(&mut app);
+    assert_eq!(to_ron(&resaved), text);
+
+    // NOTE: the rng state is saved, not reseeded, so both games keep rolling the same numbers
+    assert_eq!(resaved.rng, saved.rng);
+    let next = played.world.resource_mut::<RogueRng>().next_u64();
+    assert_eq!(app.world.resource_mut::<RogueRng>().next_u64(), next);
+}
@@ -0,0 +1,31 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+/// Where the actor is in the current turn
+#[derive(
+    Debug,
+    Default,
+    Copy,
+    Clone,
+    Ord,
+    PartialOrd,
+    Eq,
+    PartialEq,
+    Hash,
+    Component,
+    Reflect,
+    Serialize,
+    Deserialize,
+)]
+#[reflect(Component)]
+pub enum TurnState {
+    /// about to gather action points
+    #[default]
+    Collect,
+    /// has enough action points, queued until its turn to act comes
+    Wait,
+    /// acting now (one actor at a time) until it spends action points
+    Act,
+    /// done for this turn (or not enough action points to act at all)
+    End,
+}
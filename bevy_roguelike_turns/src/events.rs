@@ -0,0 +1,4 @@
+/// Every actor ended its turn and a new one starts: time passes.
+/// Sent before actors gather action points, lasting effects (regeneration, light flashes, memories) tick on it.
+#[derive(Debug, Copy, Clone)]
+pub struct TurnStartEvent;
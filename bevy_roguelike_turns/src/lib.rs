@@ -0,0 +1,12 @@
+pub use components::*;
+pub use events::*;
+pub use plugin::RoguelikeTurnsPlugin;
+pub use queue::TurnQueue;
+pub use resources::*;
+
+mod components;
+mod events;
+mod plugin;
+mod queue;
+mod resources;
+mod systems;
@@ -0,0 +1,31 @@
+use crate::{components::*, events::*, resources::*, systems::*};
+use bevy::{ecs::schedule::StateData, prelude::*};
+use bevy_roguelike_combat::AttributeType;
+use std::marker::PhantomData;
+
+/// Turns: actors gather action points every turn and act in the order of their action points
+/// until they are spent ([`TurnScheduler`]). Actions spend action points with
+/// [`bevy_roguelike_combat::SpendAPEvent`] (costs in [`ActionCosts`]).
+/// Needs [`bevy_roguelike_combat::RoguelikeCombatPlugin`] and runs without rendering.
+pub struct RoguelikeTurnsPlugin<S, A: AttributeType> {
+    pub state_running: S,
+    pub phantom: PhantomData<A>,
+}
+
+impl<S: StateData, A: AttributeType> Plugin for RoguelikeTurnsPlugin<S, A> {
+    fn build(&self, app: &mut App) {
+        app.add_system_set_to_stage(
+            CoreStage::PreUpdate,
+            SystemSet::on_update(self.state_running.clone()).with_system(turn_start::<A>),
+        )
+        .add_system_set_to_stage(
+            CoreStage::Update,
+            SystemSet::on_update(self.state_running.clone()).with_system(action_completed::<A>),
+        )
+        .init_resource::<TurnScheduler>()
+        .init_resource::<ActionCosts>()
+        .register_type::<TurnState>()
+        .register_type::<ActionCosts>()
+        .add_event::<TurnStartEvent>();
+    }
+}
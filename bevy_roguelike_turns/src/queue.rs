@@ -0,0 +1,117 @@
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+/// Priority queue of actors ready to act, keyed on their action points.
+/// The one with the most action points comes first, ties go to the lower `order`
+/// (then to the lower item), so the same game always plays out the same.
+/// Plain data, usable without a bevy app.
+#[derive(Debug, Clone)]
+pub struct TurnQueue<T: Ord> {
+    heap: BinaryHeap<(i16, Reverse<u64>, Reverse<T>)>,
+}
+
+impl<T: Ord> Default for TurnQueue<T> {
+    fn default() -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+        }
+    }
+}
+
+impl<T: Ord> TurnQueue<T> {
+    pub fn push(&mut self, item: T, action_points: i16, order: u64) {
+        self.heap
+            .push((action_points, Reverse(order), Reverse(item)));
+    }
+
+    /// next to act
+    pub fn pop(&mut self) -> Option<T> {
+        self.pop_with_points().map(|(item, _)| item)
+    }
+
+    /// next to act with the action points it was queued with
+    pub fn pop_with_points(&mut self) -> Option<(T, i16)> {
+        self.heap
+            .pop()
+            .map(|(action_points, _, Reverse(item))| (item, action_points))
+    }
+
+    pub fn peek(&self) -> Option<&T> {
+        self.heap.peek().map(|(_, _, Reverse(item))| item)
+    }
+
+    pub fn len(&self) -> usize {
+        self.heap.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.heap.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.heap.clear();
+    }
+
+    /// every queued item, next to act first. leaves the queue empty.
+    pub fn drain_ordered(&mut self) -> Vec<T> {
+        let mut items = Vec::with_capacity(self.heap.len());
+        while let Some(item) = self.pop() {
+            items.push(item);
+        }
+        items
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn most_action_points_first() {
+        let mut queue = TurnQueue::default();
+        queue.push('a', 130, 0);
+        queue.push('b', 200, 1);
+        queue.push('c', -5, 2);
+        queue.push('d', 164, 3);
+        assert_eq!(queue.len(), 4);
+        assert_eq!(queue.peek(), Some(&'b'));
+        assert_eq!(queue.pop_with_points(), Some(('b', 200)));
+        assert_eq!(queue.drain_ordered(), vec!['d', 'a', 'c']);
+        assert!(queue.is_empty());
+        assert_eq!(queue.pop(), None);
+    }
+
+    #[test]
+    fn ties_go_to_the_lower_order_then_item() {
+        let mut queue = TurnQueue::default();
+        queue.push('z', 150, 2);
+        queue.push('y', 150, 0);
+        queue.push('x', 150, 1);
+        queue.push('b', 150, 3);
+        queue.push('a', 150, 3);
+        assert_eq!(queue.drain_ordered(), vec!['y', 'x', 'z', 'a', 'b']);
+    }
+
+    #[test]
+    fn push_order_does_not_matter() {
+        let entries = [('a', 140, 4), ('b', 180, 1), ('c', 140, 2), ('d', 180, 3)];
+        let mut forward = TurnQueue::default();
+        let mut backward = TurnQueue::default();
+        for (item, ap, order) in entries {
+            forward.push(item, ap, order);
+        }
+        for (item, ap, order) in entries.into_iter().rev() {
+            backward.push(item, ap, order);
+        }
+        assert_eq!(forward.drain_ordered(), vec!['b', 'd', 'c', 'a']);
+        assert_eq!(backward.drain_ordered(), vec!['b', 'd', 'c', 'a']);
+    }
+
+    #[test]
+    fn clear() {
+        let mut queue = TurnQueue::default();
+        queue.push(1, 150, 0);
+        queue.clear();
+        assert!(queue.is_empty());
+        assert_eq!(queue.peek(), None);
+    }
+}
@@ -0,0 +1,235 @@
+use crate::{TurnQueue, TurnState};
+use bevy::{prelude::*, utils::HashMap};
+use bevy_roguelike_combat::AP_MOVE_COST_DEFAULT;
+use serde::{Deserialize, Serialize};
+
+/// Decides who acts and in which order (see [`crate::RoguelikeTurnsPlugin`]).
+/// Actors ready to act are queued by their action points ([`TurnQueue`]), ties go to the actor seen first.
+/// One actor acts at a time, it is queued again by its remaining action points after each action.
+#[derive(Resource, Debug, Default)]
+pub struct TurnScheduler {
+    queue: TurnQueue<Entity>,
+    /// actor acting now and its action points when it got to act
+    current: Option<(Entity, i16)>,
+    /// order the actors were first seen in
+    orders: HashMap<Entity, u64>,
+    order_next: u64,
+    turn: u64,
+}
+
+impl TurnScheduler {
+    /// scheduler of a game restored from a save, `turn` turns since the start
+    pub fn with_turn(turn: u64) -> Self {
+        Self {
+            turn,
+            ..Default::default()
+        }
+    }
+
+    /// turns passed since the start
+    pub fn turn(&self) -> u64 {
+        self.turn
+    }
+
+    /// actor acting now ([`TurnState::Act`])
+    pub fn current(&self) -> Option<Entity> {
+        self.current.map(|(actor, _)| actor)
+    }
+
+    /// order the actor was first seen in. tie breaker of equally ready actors.
+    pub fn order(&mut self, actor: Entity) -> u64 {
+        let order_next = &mut self.order_next;
+        *self.orders.entry(actor).or_insert_with(|| {
+            *order_next += 1;
+            *order_next - 1
+        })
+    }
+
+    /// order of an actor already seen (what a save keeps)
+    pub fn order_of(&self, actor: Entity) -> Option<u64> {
+        self.orders.get(&actor).copied()
+    }
+
+    /// Puts an actor restored from a save back in its place: its saved `order`,
+    /// queued if it was waiting or acting if it was the one acting.
+    pub fn restore(
+        &mut self,
+        actor: Entity,
+        order: Option<u64>,
+        turn_state: TurnState,
+        action_points: i16,
+    ) {
+        if let Some(order) = order {
+            self.orders.insert(actor, order);
+            self.order_next = self.order_next.max(order + 1);
+        }
+        match turn_state {
+            TurnState::Wait => self.queue(actor, action_points),
+            TurnState::Act => self.current = Some((actor, action_points)),
+            TurnState::Collect | TurnState::End => {}
+        }
+    }
+
+    pub(crate) fn turn_start(&mut self) {
+        self.turn += 1;
+        self.current = None;
+        self.queue.clear();
+    }
+
+    pub(crate) fn queue(&mut self, actor: Entity, action_points: i16) {
+        let order = self.order(actor);
+        self.queue.push(actor, action_points, order);
+    }
+
+    pub(crate) fn is_queue_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+
+    /// next queued actor with the action points it was queued with
+    /// (it might be gone, done or have spent some since)
+    pub(crate) fn pop(&mut self) -> Option<(Entity, i16)> {
+        self.queue.pop_with_points()
+    }
+
+    /// `actor` acts now having `action_points`
+    pub(crate) fn act(&mut self, actor: Entity, action_points: i16) {
+        self.current = Some((actor, action_points));
+    }
+
+    /// the current actor and its action points when it got to act. cleared.
+    pub(crate) fn take_current(&mut self) -> Option<(Entity, i16)> {
+        self.current.take()
+    }
+
+    /// forgets actors no longer around
+    pub(crate) fn retain(&mut self, is_around: impl Fn(Entity) -> bool) {
+        self.orders.retain(|e, _| is_around(*e));
+        if self.current.is_some_and(|(e, _)| !is_around(e)) {
+            self.current = None;
+        }
+    }
+}
+
+/// Action points spent on actions (attack, evasion and block costs come with the actor stats)
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Reflect, Serialize, Deserialize)]
+#[reflect(Resource)]
+pub struct ActionCosts {
+    /// moving onto a tile, multiplied by the tile movement cost
+    pub movement: i16,
+    /// taking the stairs to another dungeon floor
+    pub stairs: i16,
+}
+
+impl Default for ActionCosts {
+    fn default() -> Self {
+        Self {
+            movement: AP_MOVE_COST_DEFAULT,
+            stairs: AP_MOVE_COST_DEFAULT,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entities<const N: usize>() -> [Entity; N] {
+        std::array::from_fn(|i| Entity::from_raw(i as u32))
+    }
+
+    fn drain(scheduler: &mut TurnScheduler) -> Vec<(Entity, i16)> {
+        std::iter::from_fn(|| scheduler.pop()).collect()
+    }
+
+    #[test]
+    fn order_is_first_seen() {
+        let [a, b, c] = entities();
+        let mut scheduler = TurnScheduler::default();
+        assert_eq!(scheduler.order_of(c), None);
+        assert_eq!(scheduler.order(c), 0);
+        assert_eq!(scheduler.order(a), 1);
+        assert_eq!(scheduler.order(c), 0);
+        assert_eq!(scheduler.order(b), 2);
+        assert_eq!(scheduler.order_of(a), Some(1));
+    }
+
+    #[test]
+    fn queued_by_action_points_then_order() {
+        let [a, b, c, d] = entities();
+        let mut scheduler = TurnScheduler::default();
+        for actor in [d, c, b, a] {
+            scheduler.order(actor);
+        }
+        scheduler.queue(a, 150);
+        scheduler.queue(b, 200);
+        scheduler.queue(c, 150);
+        scheduler.queue(d, 150);
+        assert!(!scheduler.is_queue_empty());
+        assert_eq!(
+            drain(&mut scheduler),
+            vec![(b, 200), (d, 150), (c, 150), (a, 150)]
+        );
+        assert!(scheduler.is_queue_empty());
+    }
+
+    #[test]
+    fn one_actor_acts_at_a_time() {
+        let [a, b] = entities();
+        let mut scheduler = TurnScheduler::default();
+        scheduler.queue(a, 150);
+        scheduler.queue(b, 140);
+        let (actor, ap) = scheduler.pop().unwrap();
+        scheduler.act(actor, ap);
+        assert_eq!(scheduler.current(), Some(a));
+        assert_eq!(scheduler.take_current(), Some((a, 150)));
+        assert_eq!(scheduler.current(), None);
+        // NOTE: acted and still ready, queued again by what is left
+        scheduler.queue(a, 130);
+        assert_eq!(drain(&mut scheduler), vec![(b, 140), (a, 130)]);
+    }
+
+    #[test]
+    fn restore_keeps_saved_order_and_state() {
+        let [a, b, c, d] = entities();
+        let mut scheduler = TurnScheduler::with_turn(7);
+        scheduler.restore(a, Some(5), TurnState::Act, 140);
+        scheduler.restore(b, Some(9), TurnState::Wait, 150);
+        scheduler.restore(c, Some(2), TurnState::Wait, 150);
+        scheduler.restore(d, None, TurnState::End, 90);
+        assert_eq!(scheduler.turn(), 7);
+        assert_eq!(scheduler.current(), Some(a));
+        assert_eq!(scheduler.order_of(b), Some(9));
+        assert_eq!(scheduler.order_of(d), None);
+        assert_eq!(drain(&mut scheduler), vec![(c, 150), (b, 150)]);
+        // NOTE: actors seen after the load come after every restored one
+        assert_eq!(scheduler.order(d), 10);
+    }
+
+    #[test]
+    fn turn_start_clears_the_queue() {
+        let [a, b] = entities();
+        let mut scheduler = TurnScheduler::default();
+        scheduler.queue(a, 150);
+        scheduler.act(b, 150);
+        scheduler.turn_start();
+        assert_eq!(scheduler.turn(), 1);
+        assert_eq!(scheduler.current(), None);
+        assert!(scheduler.is_queue_empty());
+        assert_eq!(scheduler.order_of(a), Some(0));
+    }
+
+    #[test]
+    fn retain_forgets_actors_gone() {
+        let [a, b] = entities();
+        let mut scheduler = TurnScheduler::default();
+        scheduler.order(a);
+        scheduler.order(b);
+        scheduler.act(b, 150);
+        scheduler.retain(|e| e == a);
+        assert_eq!(scheduler.order_of(a), Some(0));
+        assert_eq!(scheduler.order_of(b), None);
+        assert_eq!(scheduler.current(), None);
+        // NOTE: orders are never reused, so ties keep going to the actors seen first
+        assert_eq!(scheduler.order(b), 2);
+    }
+}
@@ -0,0 +1,257 @@
+use crate::{events::*, resources::*, TurnState};
+use bevy::prelude::*;
+use bevy_roguelike_combat::*;
+
+/// Gives the turn to one actor at a time ([`TurnScheduler::current`]).
+/// The acting actor is queued again by its remaining action points once it spent some (or ends its turn
+/// when out of them), then the next queued one acts. When every actor ended its turn a new one starts
+/// ([`TurnStartEvent`]) and actors gather action points, those ready to act are queued, the rest skip the turn.
+/// Actors joining mid turn (back on the floor) gather right away.
+pub fn turn_start<A: AttributeType>(
+    mut actors: Query<(Entity, &mut ActionPoints<A>, &mut TurnState)>,
+    mut scheduler: ResMut<TurnScheduler>,
+    mut turn_start_writer: EventWriter<TurnStartEvent>,
+) {
+    if actors.is_empty() {
+        return;
+    }
+    if let Some((actor, action_points)) = scheduler.take_current() {
+        if let Ok((_, ap, mut ts)) = actors.get_mut(actor) {
+            if *ts == TurnState::Act {
+                // NOTE: not acted yet, or out of action points with `ActionCompletedEvent` on its way
+                if ap.current() >= action_points || ap.current() < ap.turn_ready_to_act() {
+                    scheduler.act(actor, action_points);
+                    return;
+                }
+                *ts = TurnState::Wait;
+                scheduler.queue(actor, ap.current());
+            }
+        }
+    }
+    if actors.iter().all(|(_, _, ts)| *ts == TurnState::End) {
+        scheduler.turn_start();
+        scheduler.retain(|e| actors.contains(e));
+        turn_start_writer.send(TurnStartEvent);
+        for (_, _, mut ts) in actors.iter_mut() {
+            *ts = TurnState::Collect;
+        }
+    }
+    let mut collecting: Vec<_> = actors
+        .iter()
+        .filter(|(_, _, ts)| **ts == TurnState::Collect)
+        .map(|(e, _, _)| e)
+        .collect();
+    // NOTE: gathering in the order actors were first seen so the queue order does not depend on query order
+    collecting.sort_by_cached_key(|e| scheduler.order(*e));
+    for actor in collecting {
+        if let Ok((_, mut ap, mut ts)) = actors.get_mut(actor) {
+            if ap.current_add() > ap.turn_ready_to_act() {
+                *ts = TurnState::Wait;
+                scheduler.queue(actor, ap.current());
+            } else {
+                // NOTE: not yet ready to perform turn.
+                // skip this turn.
+                *ts = TurnState::End;
+            }
+        }
+    }
+    if scheduler.is_queue_empty() {
+        // NOTE: actors left waiting or acting without the scheduler knowing (not restored with it)
+        let mut stranded: Vec<_> = actors
+            .iter()
+            .filter(|(_, _, ts)| matches!(**ts, TurnState::Wait | TurnState::Act))
+            .map(|(e, ap, _)| (e, ap.current()))
+            .collect();
+        stranded.sort_by_cached_key(|(e, _)| scheduler.order(*e));
+        for (actor, action_points) in stranded {
+            scheduler.queue(actor, action_points);
+        }
+    }
+    while let Some((actor, action_points)) = scheduler.pop() {
+        let (_, ap, mut ts) = if let Ok(actor) = actors.get_mut(actor) {
+            actor
+        } else {
+            continue;
+        };
+        if !matches!(*ts, TurnState::Wait | TurnState::Act) || ap.current() < ap.turn_ready_to_act()
+        {
+            continue;
+        }
+        if ap.current() != action_points {
+            // NOTE: spent action points while waiting (evading, stepping aside for an ally)
+            scheduler.queue(actor, ap.current());
+            continue;
+        }
+        *ts = TurnState::Act;
+        scheduler.act(actor, action_points);
+        return;
+    }
+}
+
+/// Actors out of action points end their turn and regenerate
+pub fn action_completed<A: AttributeType>(
+    mut actors: Query<(&mut TurnState, &mut HitPoints<A>)>,
+    mut action_completed_reader: EventReader<ActionCompletedEvent>,
+) {
+    for e in action_completed_reader.iter() {
+        if let Ok((mut ts, mut hp)) = actors.get_mut(e.id) {
+            *ts = TurnState::End;
+            hp.regen();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RoguelikeTurnsPlugin;
+    use bevy::reflect::FromReflect;
+    use std::{fmt::Display, marker::PhantomData};
+
+    #[derive(Debug, Clone, Eq, PartialEq, Hash)]
+    enum TestState {
+        Running,
+    }
+
+    #[derive(Debug, Default, Clone, Eq, PartialEq, Hash, Component, Reflect, FromReflect)]
+    enum Speed {
+        #[default]
+        Speed,
+    }
+    impl Display for Speed {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "spd")
+        }
+    }
+    impl strum::IntoEnumIterator for Speed {
+        type Iterator = std::iter::Once<Speed>;
+
+        fn iter() -> Self::Iterator {
+            std::iter::once(Speed::Speed)
+        }
+    }
+    impl AttributeType for Speed {}
+
+    /// every action costs the same
+    const ACTION_COST: i16 = 64;
+
+    #[derive(Resource, Default)]
+    struct Acted(Vec<Entity>);
+
+    /// acting actor spends action points like `bevy_roguelike_combat` does on a `SpendAPEvent`
+    fn act(
+        mut actors: Query<(Entity, &TurnState, &mut ActionPoints<Speed>)>,
+        scheduler: Res<TurnScheduler>,
+        mut acted: ResMut<Acted>,
+        mut action_completed_writer: EventWriter<ActionCompletedEvent>,
+    ) {
+        let acting: Vec<_> = actors
+            .iter()
+            .filter(|(_, ts, _)| **ts == TurnState::Act)
+            .map(|(e, _, _)| e)
+            .collect();
+        assert!(acting.len() <= 1, "{:?} act at once", acting);
+        assert_eq!(acting.first().copied(), scheduler.current());
+        if let Some(Ok((id, _, mut ap))) = scheduler.current().map(|e| actors.get_mut(e)) {
+            acted.0.push(id);
+            if ap.current_minus(ACTION_COST) < ap.turn_ready_to_act() {
+                action_completed_writer.send(ActionCompletedEvent { id });
+            }
+        }
+    }
+
+    /// action points gathered every turn are `AP_INCREMENT_MIN + speed`
+    fn spawn_actor(app: &mut App, speed: u8) -> Entity {
+        let attributes = Attributes::<Speed>::with_all(speed);
+        let increment = LinearFormula::new(
+            1000,
+            [Multiplier {
+                multiplier: 100,
+                attribute: Speed::Speed,
+            }],
+        );
+        app.world
+            .spawn((
+                ActionPoints::new(increment, &attributes),
+                HitPoints::new(LinearFormula::one(), LinearFormula::one(), &attributes),
+                TurnState::default(),
+            ))
+            .id()
+    }
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_state_to_stage(CoreStage::PreUpdate, TestState::Running)
+            .add_state_to_stage(CoreStage::Update, TestState::Running)
+            .add_plugins(MinimalPlugins)
+            .add_plugin(RoguelikeTurnsPlugin::<_, Speed> {
+                state_running: TestState::Running,
+                phantom: PhantomData {},
+            })
+            .add_event::<ActionCompletedEvent>()
+            .init_resource::<Acted>()
+            .add_system(act.before(action_completed::<Speed>));
+        app
+    }
+
+    #[test]
+    fn actors_act_one_at_a_time_by_action_points() {
+        let mut app = app();
+        let fast = spawn_actor(&mut app, 100);
+        let slow = spawn_actor(&mut app, 36);
+        let also_fast = spawn_actor(&mut app, 100);
+        for _ in 0..10 {
+            app.update();
+        }
+        // NOTE: turn 1: 164 action points for the fast ones (one action each), 100 is not enough for the slow one.
+        // turn 2: 328 and 200, everyone acts until below 128, requeued by what is left after every action.
+        // ties go to the actor seen first.
+        assert_eq!(
+            app.world.resource::<Acted>().0,
+            vec![
+                fast, also_fast, //
+                fast, also_fast, fast, slow, also_fast, fast, slow, also_fast,
+            ]
+        );
+        // NOTE: the first turn starts without `TurnStartEvent`, one turn passed
+        assert_eq!(app.world.resource::<TurnScheduler>().turn(), 1);
+    }
+
+    #[test]
+    fn same_game_plays_out_the_same() {
+        let run = || {
+            let mut app = app();
+            let actors: Vec<_> = [40, 120, 80, 120, 65]
+                .into_iter()
+                .map(|speed| spawn_actor(&mut app, speed))
+                .collect();
+            for _ in 0..200 {
+                app.update();
+            }
+            let acted = &app.world.resource::<Acted>().0;
+            let acted: Vec<_> = acted
+                .iter()
+                .map(|e| actors.iter().position(|a| a == e).unwrap())
+                .collect();
+            (acted, app.world.resource::<TurnScheduler>().turn())
+        };
+        let (acted, turn) = run();
+        assert!(turn > 10);
+        assert_eq!(run(), (acted, turn));
+    }
+
+    #[test]
+    fn actor_joining_mid_turn_gathers_right_away() {
+        let mut app = app();
+        let actor = spawn_actor(&mut app, 100);
+        let idle = spawn_actor(&mut app, 10);
+        app.update();
+        assert_eq!(app.world.get::<TurnState>(idle), Some(&TurnState::End));
+        assert_eq!(app.world.get::<TurnState>(actor), Some(&TurnState::End));
+
+        let joining = spawn_actor(&mut app, 100);
+        app.update();
+        assert_eq!(app.world.resource::<Acted>().0, vec![actor, joining]);
+        assert_eq!(app.world.resource::<TurnScheduler>().turn(), 0);
+    }
+}
@@ -0,0 +1,100 @@
+//! Runs every map generator across many seeds and map sizes and prints timings and map quality stats.
+//!
+//! `cargo run --release -p map_generator --bin mapgen_stats -- --seeds 50 --size 80x50 --size 160x100`
+use glam::IVec2;
+use map_generator::*;
+
+const DEFAULT_SEED_COUNT: u64 = 20;
+const DEFAULT_SIZE: IVec2 = IVec2::new(80, 50);
+/// smallest map every generator can fill (an edge wall plus a few rooms or tiles inside)
+const MIN_SIZE: IVec2 = IVec2::new(8, 8);
+const USAGE: &str = "usage: mapgen_stats [--seeds N] [--size WIDTHxHEIGHT]... (sizes from 8x8)";
+
+/// `WIDTHxHEIGHT`, at least [`MIN_SIZE`]
+fn parse_size(text: &str) -> Option<IVec2> {
+    let (x, y) = text.split_once('x')?;
+    let size = IVec2::new(x.parse().ok()?, y.parse().ok()?);
+    size.cmpge(MIN_SIZE).all().then_some(size)
+}
+
+fn main() {
+    let mut seed_count = DEFAULT_SEED_COUNT;
+    let mut sizes = vec![];
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match (arg.as_str(), args.next()) {
+            ("--seeds", Some(value)) => match value.parse() {
+                Ok(count) => seed_count = count,
+                Err(_) => return eprintln!("invalid seed count {}", value),
+            },
+            ("--size", Some(value)) => match parse_size(&value) {
+                Some(size) => sizes.push(size),
+                None => return eprintln!("invalid size {}\n{}", value, USAGE),
+            },
+            _ => return eprintln!("{}", USAGE),
+        }
+    }
+    if sizes.is_empty() {
+        sizes.push(DEFAULT_SIZE);
+    }
+
+    let generators: Vec<(&str, Box<dyn MapGenerator>)> = vec![
+        ("drunkard", Box::<DrunkardGenerator>::default()),
+        ("rooms", Box::<RoomsGenerator>::default()),
+        (
+            "rooms routed",
+            Box::new(RoomsGenerator {
+                corridors: Corridors {
+                    connection: RoomConnection::SpanningTree { loop_ratio: 0.15 },
+                    route: CorridorRoute::AStar,
+                    width: 1,
+                },
+                ..Default::default()
+            }),
+        ),
+        ("bsp", Box::<BspGenerator>::default()),
+        ("conway life", Box::<ConwayLifeGenerator>::default()),
+        (
+            "caverns",
+            Box::new(ConwayLifeGenerator {
+                fill_ratio: 0.55,
+                passes: vec![
+                    CellularPass {
+                        rule: "B5678/S45678".parse().expect("valid rule"),
+                        iter_count: 4,
+                    },
+                    CellularPass {
+                        rule: "B5678/S5678".parse().expect("valid rule"),
+                        iter_count: 2,
+                    },
+                ],
+            }),
+        ),
+        ("wfc", Box::<WfcGenerator>::default()),
+        ("empty", Box::new(EmptyGenerator {})),
+    ];
+
+    println!("{}", GeneratorReport::header());
+    for size in sizes {
+        for (name, generator) in generators.iter() {
+            println!(
+                "{}",
+                GeneratorReport::run(name, generator.as_ref(), size, 0..seed_count)
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_size_rejects_small_and_malformed_sizes() {
+        assert_eq!(parse_size("80x50"), Some(IVec2::new(80, 50)));
+        assert_eq!(parse_size("8x8"), Some(MIN_SIZE));
+        for text in ["0x0", "3x3", "8x7", "-8x8", "80", "80x", "ax50"] {
+            assert_eq!(parse_size(text), None, "{}", text);
+        }
+    }
+}
@@ -0,0 +1,239 @@
+use super::prelude::*;
+
+const WORD_BITS: usize = u64::BITS as usize;
+
+/// Set of map points backed by a bitset sized to the map (one bit per tile).
+/// Points outside of the grid are never contained.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct BitGrid {
+    size: IVec2,
+    words: Vec<u64>,
+}
+
+impl BitGrid {
+    pub fn new(size: IVec2) -> Self {
+        let len = (size.x.max(0) * size.y.max(0)) as usize;
+        Self {
+            size,
+            words: vec![0; len.div_ceil(WORD_BITS)],
+        }
+    }
+
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    pub fn is_in_bounds(&self, pt: IVec2) -> bool {
+        self.size.x > pt.x && self.size.y > pt.y && pt.x >= 0 && pt.y >= 0
+    }
+
+    fn index(&self, pt: IVec2) -> (usize, u64) {
+        let idx = (pt.y * self.size.x + pt.x) as usize;
+        (idx / WORD_BITS, 1 << (idx % WORD_BITS))
+    }
+
+    pub fn contains(&self, pt: IVec2) -> bool {
+        if !self.is_in_bounds(pt) {
+            return false;
+        }
+        let (word, bit) = self.index(pt);
+        self.words[word] & bit != 0
+    }
+
+    /// returns `true` if the point was not in the set. out of bounds points are ignored.
+    pub fn insert(&mut self, pt: IVec2) -> bool {
+        if !self.is_in_bounds(pt) {
+            return false;
+        }
+        let (word, bit) = self.index(pt);
+        let is_new = self.words[word] & bit == 0;
+        self.words[word] |= bit;
+        is_new
+    }
+
+    /// returns `true` if the point was in the set
+    pub fn remove(&mut self, pt: IVec2) -> bool {
+        if !self.is_in_bounds(pt) {
+            return false;
+        }
+        let (word, bit) = self.index(pt);
+        let was_set = self.words[word] & bit != 0;
+        self.words[word] &= !bit;
+        was_set
+    }
+
+    pub fn clear(&mut self) {
+        self.words.iter_mut().for_each(|w| *w = 0);
+    }
+
+    pub fn len(&self) -> usize {
+        self.words.iter().map(|w| w.count_ones() as usize).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.words.iter().all(|w| *w == 0)
+    }
+
+    /// points in row major order (same as [`Map::enumerate`])
+    pub fn iter(&self) -> impl Iterator<Item = IVec2> + '_ {
+        let width = self.size.x.max(1) as usize;
+        self.words
+            .iter()
+            .enumerate()
+            .filter(|(_, w)| **w != 0)
+            .flat_map(move |(wi, w)| {
+                let mut w = *w;
+                std::iter::from_fn(move || {
+                    if w == 0 {
+                        return None;
+                    }
+                    let bit = w.trailing_zeros() as usize;
+                    w &= w - 1;
+                    let idx = wi * WORD_BITS + bit;
+                    Some(IVec2::new((idx % width) as i32, (idx / width) as i32))
+                })
+            })
+    }
+
+    /// adds every point of `other`. grows to `other` size if needed (keeping own points).
+    pub fn union_with(&mut self, other: &BitGrid) {
+        self.match_size(other);
+        for (w, o) in self.words.iter_mut().zip(other.words.iter()) {
+            *w |= *o;
+        }
+    }
+
+    /// points contained in exactly one of the sets (what changed between the two).
+    /// sized to the larger of the two.
+    pub fn symmetric_difference(&self, other: &BitGrid) -> BitGrid {
+        let mut diff = self.clone();
+        diff.match_size(other);
+        let mut other = other.clone();
+        other.match_size(&diff);
+        for (w, o) in diff.words.iter_mut().zip(other.words.iter()) {
+            *w ^= *o;
+        }
+        diff
+    }
+
+    fn match_size(&mut self, other: &BitGrid) {
+        if self.size == other.size {
+            return;
+        }
+        let mut resized = BitGrid::new(self.size.max(other.size));
+        for pt in self.iter() {
+            resized.insert(pt);
+        }
+        *self = resized;
+    }
+}
+
+impl Extend<IVec2> for BitGrid {
+    fn extend<I: IntoIterator<Item = IVec2>>(&mut self, iter: I) {
+        for pt in iter {
+            self.insert(pt);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    /// row major like [`BitGrid::iter`]
+    fn row_major(points: &BTreeSet<(i32, i32)>) -> Vec<IVec2> {
+        points.iter().map(|(y, x)| IVec2::new(*x, *y)).collect()
+    }
+
+    #[test]
+    fn insert_contains_remove() {
+        let mut grid = BitGrid::new(IVec2::new(3, 2));
+        assert!(grid.is_empty());
+        assert!(grid.insert(IVec2::new(2, 1)));
+        assert!(!grid.insert(IVec2::new(2, 1)));
+        assert!(grid.contains(IVec2::new(2, 1)));
+        assert!(!grid.contains(IVec2::new(1, 2)));
+        assert_eq!(grid.len(), 1);
+        assert!(grid.remove(IVec2::new(2, 1)));
+        assert!(!grid.remove(IVec2::new(2, 1)));
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn out_of_bounds_points_are_ignored() {
+        let mut grid = BitGrid::new(IVec2::new(3, 2));
+        for pt in [
+            IVec2::new(-1, 0),
+            IVec2::new(0, -1),
+            IVec2::new(3, 0),
+            IVec2::new(0, 2),
+        ] {
+            assert!(!grid.insert(pt));
+            assert!(!grid.contains(pt));
+            assert!(!grid.remove(pt));
+        }
+        assert!(grid.is_empty());
+
+        let mut empty = BitGrid::new(IVec2::ZERO);
+        assert!(!empty.insert(IVec2::ZERO));
+        assert_eq!(empty.iter().count(), 0);
+    }
+
+    #[test]
+    fn behaves_like_a_set_across_words() {
+        // NOTE: 13 * 11 tiles span three words
+        let size = IVec2::new(13, 11);
+        let mut rng = StdRng::seed_from_u64(3);
+        let mut grid = BitGrid::new(size);
+        let mut set = BTreeSet::new();
+        for _ in 0..500 {
+            let pt = IVec2::new(rng.gen_range(0..size.x), rng.gen_range(0..size.y));
+            if rng.gen_bool(0.7) {
+                assert_eq!(grid.insert(pt), set.insert((pt.y, pt.x)));
+            } else {
+                assert_eq!(grid.remove(pt), set.remove(&(pt.y, pt.x)));
+            }
+            assert_eq!(grid.len(), set.len());
+        }
+        assert_eq!(grid.iter().collect::<Vec<_>>(), row_major(&set));
+        grid.clear();
+        assert!(grid.is_empty());
+    }
+
+    #[test]
+    fn union_grows_to_the_larger_grid() {
+        let mut small = BitGrid::new(IVec2::new(2, 2));
+        small.extend([IVec2::new(1, 1)]);
+        let mut large = BitGrid::new(IVec2::new(9, 9));
+        large.extend([IVec2::new(8, 8), IVec2::new(1, 1)]);
+
+        small.union_with(&large);
+        assert_eq!(small.size(), IVec2::new(9, 9));
+        assert_eq!(small, large);
+    }
+
+    #[test]
+    fn symmetric_difference_is_what_changed() {
+        let mut before = BitGrid::new(IVec2::new(4, 4));
+        before.extend([IVec2::new(0, 0), IVec2::new(1, 1)]);
+        let mut after = BitGrid::new(IVec2::new(5, 4));
+        after.extend([IVec2::new(1, 1), IVec2::new(4, 3)]);
+
+        let diff = before.symmetric_difference(&after);
+        assert_eq!(diff.size(), IVec2::new(5, 4));
+        assert_eq!(
+            diff.iter().collect::<Vec<_>>(),
+            vec![IVec2::new(0, 0), IVec2::new(4, 3)]
+        );
+        assert_eq!(diff, after.symmetric_difference(&before));
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let mut grid = BitGrid::new(IVec2::new(7, 3));
+        grid.extend([IVec2::new(6, 2), IVec2::new(0, 1)]);
+        let text = ron::to_string(&grid).unwrap();
+        assert_eq!(ron::from_str::<BitGrid>(&text).unwrap(), grid);
+    }
+}
@@ -0,0 +1,129 @@
+use super::corridors::carve_dog_leg;
+use super::prelude::*;
+
+const DEFAULT_MIN_LEAF_SIZE: i32 = 9;
+const DEFAULT_MAX_DEPTH: usize = 6;
+const DEFAULT_ROOM_SIZE_MIN_RATIO: f32 = 0.45;
+/// a leaf this much longer on one axis is always split across that axis
+const SPLIT_ASPECT_RATIO: f32 = 1.25;
+
+/// Binary space partition generator. Recursively splits the map,
+/// places a single room per leaf and connects sibling sub trees on the way up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct BspGenerator {
+    /// leaves are never split into parts smaller than that (walls included)
+    pub min_leaf_size: i32,
+    /// maximum depth of the partition tree
+    pub max_depth: usize,
+    /// minimal room size relative to the leaf it is placed in
+    pub room_size_min_ratio: f32,
+}
+
+impl Default for BspGenerator {
+    fn default() -> Self {
+        Self {
+            min_leaf_size: DEFAULT_MIN_LEAF_SIZE,
+            max_depth: DEFAULT_MAX_DEPTH,
+            room_size_min_ratio: DEFAULT_ROOM_SIZE_MIN_RATIO,
+        }
+    }
+}
+
+impl MapGenerator for BspGenerator {
+    fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
+        let mut map = Map::filled_with(size, Tile::Wall);
+        self.build(rng, &mut map, Rect::new(IVec2::ZERO, size), 0);
+        map
+    }
+}
+
+impl BspGenerator {
+    /// returns rooms placed in the sub tree
+    fn build(&self, rng: &mut StdRng, map: &mut Map, area: Rect, depth: usize) -> Vec<Rect> {
+        if let Some((a, b)) = self.split(rng, area, depth) {
+            let mut rooms = self.build(rng, map, a, depth + 1);
+            let rooms_b = self.build(rng, map, b, depth + 1);
+            if let Some((from, to)) = closest_rooms(&rooms, &rooms_b) {
+                carve_dog_leg(map, from, to, rng.gen_bool(0.5));
+            }
+            rooms.extend(rooms_b);
+            rooms
+        } else {
+            let room = self.place_room(rng, area);
+            room.for_each(|pt| {
+                map[pt] = Tile::Floor;
+            });
+            vec![room]
+        }
+    }
+
+    fn split(&self, rng: &mut StdRng, area: Rect, depth: usize) -> Option<(Rect, Rect)> {
+        let min = i32::max(self.min_leaf_size, 5);
+        if depth >= self.max_depth {
+            return None;
+        }
+        let can_split_x = area.size.x >= min * 2;
+        let can_split_y = area.size.y >= min * 2;
+        let split_x = match (can_split_x, can_split_y) {
+            (false, false) => return None,
+            (true, false) => true,
+            (false, true) => false,
+            (true, true) => {
+                if area.size.x as f32 > area.size.y as f32 * SPLIT_ASPECT_RATIO {
+                    true
+                } else if area.size.y as f32 > area.size.x as f32 * SPLIT_ASPECT_RATIO {
+                    false
+                } else {
+                    rng.gen_bool(0.5)
+                }
+            }
+        };
+        if split_x {
+            let at = rng.gen_range(min..=area.size.x - min);
+            Some((
+                Rect::new(area.start, IVec2::new(at, area.size.y)),
+                Rect::new(
+                    IVec2::new(area.start.x + at, area.start.y),
+                    IVec2::new(area.size.x - at, area.size.y),
+                ),
+            ))
+        } else {
+            let at = rng.gen_range(min..=area.size.y - min);
+            Some((
+                Rect::new(area.start, IVec2::new(area.size.x, at)),
+                Rect::new(
+                    IVec2::new(area.start.x, area.start.y + at),
+                    IVec2::new(area.size.x, area.size.y - at),
+                ),
+            ))
+        }
+    }
+
+    /// room is kept one tile away from the leaf bounds, so there is always a wall between rooms
+    fn place_room(&self, rng: &mut StdRng, leaf: Rect) -> Rect {
+        let max = IVec2::max(leaf.size - 2, IVec2::ONE);
+        let min = IVec2::min(
+            IVec2::max(
+                (max.as_vec2() * self.room_size_min_ratio).as_ivec2(),
+                IVec2::splat(3),
+            ),
+            max,
+        );
+        let size = IVec2::new(rng.gen_range(min.x..=max.x), rng.gen_range(min.y..=max.y));
+        let start = IVec2::new(
+            rng.gen_range(leaf.start.x + 1..=leaf.start.x + 1 + max.x - size.x),
+            rng.gen_range(leaf.start.y + 1..=leaf.start.y + 1 + max.y - size.y),
+        );
+        Rect::new(start, size)
+    }
+}
+
+fn closest_rooms(a: &[Rect], b: &[Rect]) -> Option<(IVec2, IVec2)> {
+    a.iter()
+        .flat_map(|ra| b.iter().map(move |rb| (ra.get_center(), rb.get_center())))
+        .min_by_key(|(ca, cb)| {
+            let d = *ca - *cb;
+            d.dot(d)
+        })
+}
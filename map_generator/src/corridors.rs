@@ -0,0 +1,298 @@
+use super::prelude::*;
+use super::{a_star, Neighborhood};
+
+/// cost of digging through a wall when routing around rooms.
+/// existing floor is cheaper so corridors tend to merge.
+const ROUTE_WALL_COST: i32 = 2;
+const ROUTE_FLOOR_COST: i32 = 1;
+
+/// Which rooms get connected with each other
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub enum RoomConnection {
+    /// every room to the next one sorted by center height
+    #[default]
+    Sequential,
+    /// minimum spanning tree over room centers (every room reachable with the shortest corridors)
+    /// plus `loop_ratio` extra connections per room forming cycles
+    SpanningTree { loop_ratio: f32 },
+}
+
+/// How a single corridor between two room centers is carved
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize)]
+pub enum CorridorRoute {
+    /// straight line, cutting through anything on the way
+    #[default]
+    Straight,
+    /// L shaped, bending horizontally or vertically first at random
+    DogLeg,
+    /// A* routed around other rooms (dog leg if there is no way around)
+    AStar,
+}
+
+/// Corridor carving strategy for room based generators
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Corridors {
+    pub connection: RoomConnection,
+    pub route: CorridorRoute,
+    /// corridor width in tiles
+    pub width: i32,
+}
+
+impl Default for Corridors {
+    fn default() -> Self {
+        Self {
+            connection: RoomConnection::default(),
+            route: CorridorRoute::default(),
+            width: 1,
+        }
+    }
+}
+
+impl Corridors {
+    pub fn carve(&self, rng: &mut StdRng, map: &mut Map, rooms: &[Rect]) {
+        for (a, b) in self.connection.pairs(rng, rooms) {
+            let path = self.route.path(rng, map, rooms, a, b);
+            carve_path(map, &path, self.width);
+        }
+    }
+}
+
+impl RoomConnection {
+    /// indices of rooms to connect
+    fn pairs(&self, rng: &mut StdRng, rooms: &[Rect]) -> Vec<(usize, usize)> {
+        match self {
+            RoomConnection::Sequential => {
+                let mut order: Vec<_> = (0..rooms.len()).collect();
+                order.sort_by_key(|i| rooms[*i].get_center().y);
+                order.windows(2).map(|w| (w[0], w[1])).collect()
+            }
+            RoomConnection::SpanningTree { loop_ratio } => {
+                let distance = |a: usize, b: usize| {
+                    let d = rooms[a].get_center() - rooms[b].get_center();
+                    d.dot(d)
+                };
+                let mut edges: Vec<_> = (0..rooms.len())
+                    .flat_map(|a| (a + 1..rooms.len()).map(move |b| (a, b)))
+                    .collect();
+                edges.sort_by_key(|(a, b)| distance(*a, *b));
+
+                // NOTE: kruskal with a disjoint set over room indices
+                let mut parents: Vec<_> = (0..rooms.len()).collect();
+                let mut tree = vec![];
+                let mut rest = vec![];
+                for (a, b) in edges {
+                    let (ra, rb) = (root(&mut parents, a), root(&mut parents, b));
+                    if ra != rb {
+                        parents[ra] = rb;
+                        tree.push((a, b));
+                    } else {
+                        rest.push((a, b));
+                    }
+                }
+
+                // NOTE: loops are picked among the shorter leftover connections to keep them local
+                let loop_count = (rooms.len() as f32 * loop_ratio).round() as usize;
+                rest.truncate(rooms.len() * 2);
+                rest.shuffle(rng);
+                tree.extend(rest.into_iter().take(loop_count));
+                tree
+            }
+        }
+    }
+}
+
+impl CorridorRoute {
+    fn path(&self, rng: &mut StdRng, map: &Map, rooms: &[Rect], a: usize, b: usize) -> Vec<IVec2> {
+        let from = rooms[a].get_center();
+        let to = rooms[b].get_center();
+        match self {
+            CorridorRoute::Straight => walk(from, to),
+            CorridorRoute::DogLeg => dog_leg(from, to, rng.gen_bool(0.5)),
+            CorridorRoute::AStar => {
+                let cost = |pt: IVec2| {
+                    if map.is_edge(pt) {
+                        return None;
+                    }
+                    let in_other_room = rooms
+                        .iter()
+                        .enumerate()
+                        .any(|(i, r)| i != a && i != b && r.contains(pt));
+                    if in_other_room {
+                        None
+                    } else if map[pt].is_passable() {
+                        Some(ROUTE_FLOOR_COST)
+                    } else {
+                        Some(ROUTE_WALL_COST)
+                    }
+                };
+                a_star(map.size(), from, to, Neighborhood::Four, cost)
+                    .unwrap_or_else(|| dog_leg(from, to, rng.gen_bool(0.5)))
+            }
+        }
+    }
+}
+
+/// disjoint set root (with path halving)
+fn root(parents: &mut [usize], i: usize) -> usize {
+    let mut i = i;
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+fn walk(from: IVec2, to: IVec2) -> Vec<IVec2> {
+    WalkGrid::new((from.x, from.y), (to.x, to.y))
+        .map(|(x, y)| IVec2::new(x, y))
+        .collect()
+}
+
+fn dog_leg(from: IVec2, to: IVec2, horizontal_first: bool) -> Vec<IVec2> {
+    let corner = if horizontal_first {
+        IVec2::new(to.x, from.y)
+    } else {
+        IVec2::new(from.x, to.y)
+    };
+    let mut path = walk(from, corner);
+    path.extend(walk(corner, to));
+    path
+}
+
+/// L shaped corridor between two points
+pub(crate) fn carve_dog_leg(map: &mut Map, from: IVec2, to: IVec2, horizontal_first: bool) {
+    carve_path(map, &dog_leg(from, to, horizontal_first), 1);
+}
+
+/// carves floor along the path. wider corridors grow right and up, the map edge is kept intact.
+fn carve_path(map: &mut Map, path: &[IVec2], width: i32) {
+    for pt in path {
+        for dy in 0..width.max(1) {
+            for dx in 0..width.max(1) {
+                let pt = *pt + IVec2::new(dx, dy);
+                if map.is_in_bounds(pt) && !map.is_edge(pt) {
+                    map[pt] = Tile::Floor;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MapRegions;
+
+    fn rooms_map(size: IVec2, rooms: &[Rect]) -> Map {
+        let mut map = Map::filled_with(size, Tile::Wall);
+        for room in rooms {
+            room.for_each(|pt| map[pt] = Tile::Floor);
+        }
+        map
+    }
+
+    fn rooms() -> Vec<Rect> {
+        vec![
+            Rect::new(IVec2::new(1, 10), IVec2::new(3, 3)),
+            Rect::new(IVec2::new(10, 1), IVec2::new(3, 3)),
+            Rect::new(IVec2::new(12, 12), IVec2::new(3, 3)),
+            Rect::new(IVec2::new(2, 2), IVec2::new(3, 3)),
+        ]
+    }
+
+    #[test]
+    fn sequential_connects_rooms_by_center_height() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let pairs = RoomConnection::Sequential.pairs(&mut rng, &rooms());
+        assert_eq!(pairs, vec![(1, 3), (3, 0), (0, 2)]);
+    }
+
+    #[test]
+    fn spanning_tree_connects_every_room() {
+        let rooms = rooms();
+        let mut rng = StdRng::seed_from_u64(1);
+        let tree = RoomConnection::SpanningTree { loop_ratio: 0. }.pairs(&mut rng, &rooms);
+        assert_eq!(tree.len(), rooms.len() - 1);
+        let mut parents: Vec<_> = (0..rooms.len()).collect();
+        for (a, b) in tree {
+            let (ra, rb) = (root(&mut parents, a), root(&mut parents, b));
+            assert_ne!(ra, rb, "spanning tree has a cycle");
+            parents[ra] = rb;
+        }
+
+        let with_loops = RoomConnection::SpanningTree { loop_ratio: 0.5 }.pairs(&mut rng, &rooms);
+        assert_eq!(with_loops.len(), rooms.len() - 1 + 2);
+    }
+
+    #[test]
+    fn every_route_connects_all_rooms() {
+        let rooms = rooms();
+        for route in [
+            CorridorRoute::Straight,
+            CorridorRoute::DogLeg,
+            CorridorRoute::AStar,
+        ] {
+            let mut map = rooms_map(IVec2::new(16, 16), &rooms);
+            let corridors = Corridors {
+                route,
+                ..Default::default()
+            };
+            corridors.carve(&mut StdRng::seed_from_u64(1), &mut map, &rooms);
+            assert!(
+                MapRegions::from_map(&map).is_connected(),
+                "{:?}\n{}",
+                route,
+                map
+            );
+        }
+    }
+
+    #[test]
+    fn a_star_routes_around_other_rooms() {
+        let rooms = [
+            Rect::new(IVec2::new(1, 1), IVec2::new(3, 3)),
+            Rect::new(IVec2::new(9, 1), IVec2::new(3, 3)),
+            Rect::new(IVec2::new(5, 1), IVec2::new(3, 4)),
+        ];
+        let mut map = Map::filled_with(IVec2::new(13, 7), Tile::Wall);
+        let corridors = Corridors {
+            connection: RoomConnection::SpanningTree { loop_ratio: 0. },
+            route: CorridorRoute::AStar,
+            width: 1,
+        };
+        let path = corridors
+            .route
+            .path(&mut StdRng::seed_from_u64(1), &map, &rooms, 0, 1);
+        assert_eq!(path.first(), Some(&rooms[0].get_center()));
+        assert_eq!(path.last(), Some(&rooms[1].get_center()));
+        assert!(path.iter().all(|pt| !rooms[2].contains(*pt)));
+
+        carve_path(&mut map, &path, corridors.width);
+        assert!(map.get_edge().iter().all(|pt| map[*pt] == Tile::Wall));
+    }
+
+    #[test]
+    fn wide_corridors_keep_the_map_edge() {
+        let mut map = Map::filled_with(IVec2::new(8, 6), Tile::Wall);
+        carve_path(&mut map, &walk(IVec2::new(1, 3), IVec2::new(5, 3)), 2);
+        assert_eq!(
+            map.to_string(),
+            "########\n#......#\n#......#\n########\n########\n########\n"
+        );
+    }
+
+    #[test]
+    fn dog_leg_bends_once() {
+        let from = IVec2::new(1, 1);
+        let to = IVec2::new(4, 3);
+        let horizontal = dog_leg(from, to, true);
+        assert!(horizontal.contains(&IVec2::new(4, 1)));
+        let vertical = dog_leg(from, to, false);
+        assert!(vertical.contains(&IVec2::new(1, 3)));
+        for path in [horizontal, vertical] {
+            assert_eq!(path.first(), Some(&from));
+            assert_eq!(path.last(), Some(&to));
+        }
+    }
+}
@@ -0,0 +1,243 @@
+use super::prelude::*;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// coefficient used to turn an approach map into a flee map.
+/// values lower than -1. make actors prefer distant escape routes over running into corners
+const FLEE_COEFFICIENT: f32 = -1.2;
+
+/// Which neighboring tiles can be reached in a single step
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum Neighborhood {
+    /// up, down, left and right
+    #[default]
+    Four,
+    /// including diagonals
+    Eight,
+}
+
+impl Neighborhood {
+    pub fn deltas(&self) -> Vec<IVec2> {
+        match self {
+            Neighborhood::Four => Map::get_wasd_neighbor_deltas().to_vec(),
+            Neighborhood::Eight => Map::get_neighbor_deltas().to_vec(),
+        }
+    }
+}
+
+/// Distance (or cost) to the closest source for every tile of the map.
+/// Lower is closer. Can be rolled downhill to approach sources or uphill to run away from them.
+#[derive(Debug, Clone)]
+pub struct DijkstraMap {
+    size: IVec2,
+    neighborhood: Neighborhood,
+    distances: Vec<Option<i32>>,
+}
+
+impl DijkstraMap {
+    /// uses tile movement cost, impassable tiles are skipped
+    pub fn new(map: &Map, sources: &[IVec2], neighborhood: Neighborhood) -> Self {
+        Self::with_cost(
+            map.size(),
+            sources.iter().map(|s| (*s, 0)),
+            neighborhood,
+            |pt| map[pt].movement_cost(),
+        )
+    }
+
+    /// `sources` start with a specified distance (usually 0).
+    /// `cost` returns cost to enter the tile or `None` if the tile is impassable.
+    pub fn with_cost(
+        size: IVec2,
+        sources: impl IntoIterator<Item = (IVec2, i32)>,
+        neighborhood: Neighborhood,
+        cost: impl Fn(IVec2) -> Option<i32>,
+    ) -> Self {
+        let mut dmap = Self {
+            size,
+            neighborhood,
+            distances: vec![None; (size.x * size.y) as usize],
+        };
+        let mut frontier = BinaryHeap::new();
+        for (pt, distance) in sources {
+            if let Some(idx) = dmap.get_index(pt) {
+                if dmap.distances[idx].is_none_or(|d| distance < d) {
+                    dmap.distances[idx] = Some(distance);
+                    frontier.push((Reverse(distance), pt.x, pt.y));
+                }
+            }
+        }
+        let deltas = neighborhood.deltas();
+        while let Some((Reverse(distance), x, y)) = frontier.pop() {
+            let pt = IVec2::new(x, y);
+            if dmap.get(pt).is_some_and(|d| d < distance) {
+                continue;
+            }
+            for nb in deltas.iter().map(|d| pt + *d) {
+                let idx = if let Some(idx) = dmap.get_index(nb) {
+                    idx
+                } else {
+                    continue;
+                };
+                if let Some(step) = cost(nb) {
+                    let next = distance + step;
+                    if dmap.distances[idx].is_none_or(|d| next < d) {
+                        dmap.distances[idx] = Some(next);
+                        frontier.push((Reverse(next), nb.x, nb.y));
+                    }
+                }
+            }
+        }
+        dmap
+    }
+
+    /// Map to roll downhill when running away from the sources of this map.
+    /// Unlike simply going uphill it prefers open areas over dead ends.
+    pub fn flee(&self, cost: impl Fn(IVec2) -> Option<i32>) -> Self {
+        Self::with_cost(
+            self.size,
+            self.enumerate()
+                .map(|(pt, d)| (pt, (d as f32 * FLEE_COEFFICIENT) as i32)),
+            self.neighborhood,
+            cost,
+        )
+    }
+
+    /// distance to the closest source. `None` if unreachable.
+    pub fn get(&self, pt: IVec2) -> Option<i32> {
+        self.get_index(pt).and_then(|idx| self.distances[idx])
+    }
+
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    pub fn neighborhood(&self) -> Neighborhood {
+        self.neighborhood
+    }
+
+    /// enumerates reachable tiles and their distances
+    pub fn enumerate(&self) -> impl Iterator<Item = (IVec2, i32)> + '_ {
+        let width = self.size.x;
+        self.distances
+            .iter()
+            .enumerate()
+            .filter_map(move |(idx, d)| {
+                d.map(|d| (IVec2::new(idx as i32 % width, idx as i32 / width), d))
+            })
+    }
+
+    /// reachable tile with the greatest distance
+    pub fn farthest(&self) -> Option<(IVec2, i32)> {
+        self.enumerate().max_by_key(|(_, d)| *d)
+    }
+
+    /// neighboring tile with the lowest distance. `None` if already at the bottom.
+    pub fn step_downhill(&self, pt: IVec2) -> Option<IVec2> {
+        let mut best = self.get(pt)?;
+        let mut step = None;
+        for nb in self.neighborhood.deltas().into_iter().map(|d| pt + d) {
+            if let Some(d) = self.get(nb) {
+                if d < best {
+                    best = d;
+                    step = Some(nb);
+                }
+            }
+        }
+        step
+    }
+
+    /// neighboring tile with the highest distance. `None` if already at the top.
+    pub fn step_uphill(&self, pt: IVec2) -> Option<IVec2> {
+        let mut best = self.get(pt)?;
+        let mut step = None;
+        for nb in self.neighborhood.deltas().into_iter().map(|d| pt + d) {
+            if let Some(d) = self.get(nb) {
+                if d > best {
+                    best = d;
+                    step = Some(nb);
+                }
+            }
+        }
+        step
+    }
+
+    fn get_index(&self, pt: IVec2) -> Option<usize> {
+        if pt.x < 0 || pt.y < 0 || pt.x >= self.size.x || pt.y >= self.size.y {
+            None
+        } else {
+            Some((pt.y * self.size.x + pt.x) as usize)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn corridor() -> Map {
+        "#######\n#..:..#\n#######\n".parse().unwrap()
+    }
+
+    #[test]
+    fn distances_follow_movement_cost() {
+        let map = corridor();
+        let dmap = DijkstraMap::new(&map, &[IVec2::new(1, 1)], Neighborhood::Four);
+        let distances: Vec<_> = (0..7).map(|x| dmap.get(IVec2::new(x, 1))).collect();
+        assert_eq!(
+            distances,
+            vec![None, Some(0), Some(1), Some(3), Some(4), Some(5), None]
+        );
+        assert_eq!(dmap.get(IVec2::new(-1, 1)), None);
+        assert_eq!(dmap.farthest(), Some((IVec2::new(5, 1), 5)));
+        assert_eq!(dmap.enumerate().count(), 5);
+    }
+
+    #[test]
+    fn closest_source_wins() {
+        let map = corridor();
+        let dmap = DijkstraMap::with_cost(
+            map.size(),
+            [(IVec2::new(1, 1), 0), (IVec2::new(5, 1), 1)],
+            Neighborhood::Four,
+            |pt| map[pt].movement_cost(),
+        );
+        let distances: Vec<_> = (1..6).map(|x| dmap.get(IVec2::new(x, 1))).collect();
+        assert_eq!(distances, vec![Some(0), Some(1), Some(3), Some(2), Some(1)]);
+    }
+
+    #[test]
+    fn neighborhood_decides_diagonal_steps() {
+        let map: Map = "#####\n#...#\n#...#\n#...#\n#####\n".parse().unwrap();
+        let center = [IVec2::new(2, 2)];
+        let four = DijkstraMap::new(&map, &center, Neighborhood::Four);
+        let eight = DijkstraMap::new(&map, &center, Neighborhood::Eight);
+        assert_eq!(four.get(IVec2::new(1, 1)), Some(2));
+        assert_eq!(eight.get(IVec2::new(1, 1)), Some(1));
+        assert_eq!(
+            eight.step_downhill(IVec2::new(3, 3)),
+            Some(IVec2::new(2, 2))
+        );
+    }
+
+    #[test]
+    fn steps_roll_down_and_up_hill() {
+        let map = corridor();
+        let dmap = DijkstraMap::new(&map, &[IVec2::new(1, 1)], Neighborhood::Four);
+        assert_eq!(dmap.step_downhill(IVec2::new(5, 1)), Some(IVec2::new(4, 1)));
+        assert_eq!(dmap.step_downhill(IVec2::new(1, 1)), None);
+        assert_eq!(dmap.step_uphill(IVec2::new(1, 1)), Some(IVec2::new(2, 1)));
+        assert_eq!(dmap.step_uphill(IVec2::new(5, 1)), None);
+        assert_eq!(dmap.step_downhill(IVec2::new(0, 1)), None);
+    }
+
+    #[test]
+    fn flee_leads_away_from_sources() {
+        let map = corridor();
+        let dmap = DijkstraMap::new(&map, &[IVec2::new(1, 1)], Neighborhood::Four);
+        let flee = dmap.flee(|pt| map[pt].movement_cost());
+        assert_eq!(flee.step_downhill(IVec2::new(2, 1)), Some(IVec2::new(3, 1)));
+        assert_eq!(flee.step_downhill(IVec2::new(4, 1)), Some(IVec2::new(5, 1)));
+        assert_eq!(flee.step_downhill(IVec2::new(5, 1)), None);
+    }
+}
@@ -3,8 +3,12 @@ use super::prelude::*;
 const DEFAULT_WALK_RATIO: f32 = 0.015;
 const DEFAULT_FLOOR_RATIO: f32 = 0.6;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct DrunkardGenerator {
+    /// steps of a single walk per map tile (every walk takes at least one step)
     pub walk_ratio: f32,
+    /// floor tiles to carve per map tile (at most the whole map except its edge)
     pub floor_ratio: f32,
 }
 
@@ -20,11 +24,36 @@ impl Default for DrunkardGenerator {
 impl MapGenerator for DrunkardGenerator {
     fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
         let mut map = Map::filled_with(size, Tile::Wall);
+        self.modify(rng, &mut map);
+        map
+    }
+}
+
+/// Keeps carving drunkard walks until the floor ratio is reached.
+/// Existing floor is counted in, so use a higher `floor_ratio` to roughen an existing map.
+impl MapModifier for DrunkardGenerator {
+    fn modify(&self, rng: &mut StdRng, map: &mut Map) {
+        let size = map.size();
+        // NOTE: walks never carve the map edge
+        let interior = ((size.x - 2).max(0) * (size.y - 2).max(0)) as usize;
+        if interior == 0 {
+            return;
+        }
         let mut room_centers = Vec::new();
-        let walk_steps = ((size.x * size.y) as f32 * self.walk_ratio) as usize;
-        let desired_floor = ((size.x * size.y) as f32 * self.floor_ratio) as usize;
+        // NOTE: ratios come from data presets. at least a step per walk
+        // and never more floor than the interior can hold (the walks would go on forever)
+        let walk_steps = usize::max(((size.x * size.y) as f32 * self.walk_ratio) as usize, 1);
+        let desired_floor = usize::min(
+            ((size.x * size.y) as f32 * self.floor_ratio) as usize,
+            interior,
+        );
+        let interior_floor = |map: &Map| {
+            map.enumerate()
+                .filter(|(pt, t)| **t == Tile::Floor && !map.is_edge(*pt))
+                .count()
+        };
 
-        while map.iter().filter(|t| **t == Tile::Floor).count() < desired_floor {
+        while interior_floor(map) < desired_floor {
             let mut from = IVec2::new(
                 rng.gen_range(1..map.size().x - 1),
                 rng.gen_range(1..map.size().y - 1),
@@ -32,11 +61,9 @@ impl MapGenerator for DrunkardGenerator {
             room_centers.push(from);
             let stagger_count = usize::max(walk_steps / 10, 5);
             for _ in 0..stagger_count {
-                from = walk(from, walk_steps, rng, &mut map);
+                from = walk(from, walk_steps, rng, map);
             }
         }
-
-        map
     }
 }
 
@@ -64,3 +91,58 @@ fn walk(from: IVec2, max_step: usize, rng: &mut StdRng, map: &mut Map) -> IVec2
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen(generator: DrunkardGenerator, size: IVec2) -> Map {
+        generator.gen(&mut StdRng::seed_from_u64(7), size)
+    }
+
+    #[test]
+    fn tiny_maps_stay_walls() {
+        for size in [IVec2::new(0, 0), IVec2::new(2, 9), IVec2::new(9, 1)] {
+            let map = gen(DrunkardGenerator::default(), size);
+            assert!(map.iter().all(|t| *t == Tile::Wall));
+        }
+    }
+
+    #[test]
+    fn short_walks_still_carve() {
+        let generator = DrunkardGenerator {
+            walk_ratio: 0.,
+            ..Default::default()
+        };
+        let map = gen(generator, IVec2::new(3, 3));
+        assert_eq!(map[IVec2::new(1, 1)], Tile::Floor);
+        let map = gen(DrunkardGenerator::default(), IVec2::new(5, 5));
+        assert!(map.iter().filter(|t| **t == Tile::Floor).count() >= 5);
+    }
+
+    #[test]
+    fn floor_ratio_is_capped_at_the_interior() {
+        let generator = DrunkardGenerator {
+            floor_ratio: 2.,
+            ..Default::default()
+        };
+        let map = gen(generator, IVec2::new(12, 8));
+        for (pt, tile) in map.enumerate() {
+            let expected = if map.is_edge(pt) {
+                Tile::Wall
+            } else {
+                Tile::Floor
+            };
+            assert_eq!(*tile, expected);
+        }
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let size = IVec2::new(40, 30);
+        assert_eq!(
+            gen(DrunkardGenerator::default(), size),
+            gen(DrunkardGenerator::default(), size)
+        );
+    }
+}
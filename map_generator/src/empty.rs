@@ -1,5 +1,6 @@
 use super::prelude::*;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EmptyGenerator {}
 
 impl MapGenerator for EmptyGenerator {
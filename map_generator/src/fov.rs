@@ -0,0 +1,341 @@
+use super::prelude::*;
+use super::BitGrid;
+use line_drawing::{BresenhamCircle, Supercover};
+use std::ops::RangeInclusive;
+
+/// How visible tiles are found
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FovAlgorithm {
+    /// Symmetric shadowcasting. A floor tile is visible from another exactly when the other is visible from it
+    /// (as long as both are in each other's radius) and no tile with a clear line of sight is missed.
+    #[default]
+    Shadowcasting,
+    /// Lines cast from the center to every point of the radius circle.
+    /// Cheap looking but asymmetric and some tiles inside the radius are never reached.
+    RayCasting,
+}
+
+/// Which sight blocking tiles (walls, closed doors) are lit
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WallLighting {
+    /// every wall the field of view reaches
+    #[default]
+    All,
+    /// only walls next to a visible floor tile (no wall pieces lit through gaps in the dark)
+    NextToVisibleFloor,
+}
+
+/// Field of view computation options
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct FovOptions {
+    pub algorithm: FovAlgorithm,
+    pub walls: WallLighting,
+}
+
+impl FovOptions {
+    /// tiles visible from `origin` within `radius` (the origin included). sized to the map.
+    pub fn compute(&self, map: &Map, origin: IVec2, radius: i32) -> BitGrid {
+        let mut visible = match self.algorithm {
+            FovAlgorithm::Shadowcasting => shadowcast(map, origin, radius),
+            FovAlgorithm::RayCasting => ray_cast(map, origin, radius),
+        };
+        if self.walls == WallLighting::NextToVisibleFloor {
+            let is_lit = |pt: IVec2| {
+                pt == origin
+                    || !map[pt].blocks_sight()
+                    || Map::get_neighbor_deltas()
+                        .iter()
+                        .map(|d| pt + *d)
+                        .any(|nb| visible.contains(nb) && !map[nb].blocks_sight())
+            };
+            let unlit: Vec<_> = visible.iter().filter(|pt| !is_lit(*pt)).collect();
+            for pt in unlit {
+                visible.remove(pt);
+            }
+        }
+        visible
+    }
+}
+
+fn ray_cast(map: &Map, origin: IVec2, radius: i32) -> BitGrid {
+    let mut visible = BitGrid::new(map.size());
+    for (xo, yo) in BresenhamCircle::new(origin.x, origin.y, radius) {
+        for pt in Supercover::new((origin.x, origin.y), (xo, yo))
+            .map(|(x, y)| IVec2::new(x, y))
+            .filter(|p| map.is_in_bounds(*p))
+        {
+            visible.insert(pt);
+            if map[pt].blocks_sight() {
+                break;
+            }
+        }
+    }
+    visible
+}
+
+/// Rational slope `num / den` (`den` is positive) of a row in a quadrant
+#[derive(Debug, Clone, Copy)]
+struct Slope {
+    num: i32,
+    den: i32,
+}
+
+impl Slope {
+    /// slope through the left edge of the tile at `col`
+    fn of_tile(depth: i32, col: i32) -> Self {
+        Self {
+            num: 2 * col - 1,
+            den: 2 * depth,
+        }
+    }
+}
+
+/// Tiles at the same distance from the origin between two slopes
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    depth: i32,
+    start: Slope,
+    end: Slope,
+}
+
+impl Row {
+    fn cols(&self) -> RangeInclusive<i32> {
+        // NOTE: depth * start rounded with ties up, depth * end rounded with ties down
+        let min = (2 * self.depth * self.start.num + self.start.den).div_euclid(2 * self.start.den);
+        let max = -(self.end.den - 2 * self.depth * self.end.num).div_euclid(2 * self.end.den);
+        min..=max
+    }
+
+    /// whether the tile center is inside the row slopes (what makes visibility symmetric)
+    fn is_symmetric(&self, col: i32) -> bool {
+        col * self.start.den >= self.depth * self.start.num
+            && col * self.end.den <= self.depth * self.end.num
+    }
+
+    fn next(&self) -> Self {
+        Self {
+            depth: self.depth + 1,
+            ..*self
+        }
+    }
+}
+
+/// Symmetric shadowcasting by Albert Ford (https://www.albertford.com/shadowcasting/).
+/// Every quadrant is scanned row by row, rows are split by walls into narrower ones.
+fn shadowcast(map: &Map, origin: IVec2, radius: i32) -> BitGrid {
+    let mut visible = BitGrid::new(map.size());
+    if !map.is_in_bounds(origin) {
+        return visible;
+    }
+    visible.insert(origin);
+    let is_in_radius = |pt: IVec2| {
+        let d = pt - origin;
+        d.x * d.x + d.y * d.y <= radius * radius + radius
+    };
+    let is_wall = |pt: IVec2| !map.is_in_bounds(pt) || map[pt].blocks_sight();
+    let quadrants: [fn(i32, i32) -> IVec2; 4] = [
+        |depth, col| IVec2::new(col, -depth),
+        |depth, col| IVec2::new(col, depth),
+        |depth, col| IVec2::new(depth, col),
+        |depth, col| IVec2::new(-depth, col),
+    ];
+    for transform in quadrants {
+        let mut rows = vec![Row {
+            depth: 1,
+            start: Slope { num: -1, den: 1 },
+            end: Slope { num: 1, den: 1 },
+        }];
+        while let Some(mut row) = rows.pop() {
+            if row.depth > radius {
+                continue;
+            }
+            let mut prev_is_wall = None;
+            for col in row.cols() {
+                let pt = origin + transform(row.depth, col);
+                let wall = is_wall(pt);
+                if (wall || row.is_symmetric(col)) && map.is_in_bounds(pt) && is_in_radius(pt) {
+                    visible.insert(pt);
+                }
+                if prev_is_wall == Some(true) && !wall {
+                    row.start = Slope::of_tile(row.depth, col);
+                }
+                if prev_is_wall == Some(false) && wall {
+                    rows.push(Row {
+                        end: Slope::of_tile(row.depth, col),
+                        ..row.next()
+                    });
+                }
+                prev_is_wall = Some(wall);
+            }
+            if prev_is_wall == Some(false) {
+                rows.push(row.next());
+            }
+        }
+    }
+    visible
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ConwayLifeGenerator, DrunkardGenerator, EmptyGenerator, Prefab, PrefabStamper,
+        RoomsGenerator,
+    };
+
+    const RADIUS: i32 = 6;
+
+    fn prefabs() -> Vec<Prefab> {
+        [
+            include_str!("../../assets/prefabs/boss_arena.prefab.ron"),
+            include_str!("../../assets/prefabs/shrine.prefab.ron"),
+            include_str!("../../assets/prefabs/treasure_vault.prefab.ron"),
+        ]
+        .into_iter()
+        .map(|text| ron::from_str(text).unwrap())
+        .collect()
+    }
+
+    /// random maps of every kind plus the same maps with prefab rooms (doors, water...) stamped in
+    fn maps() -> Vec<Map> {
+        let size = IVec2::new(32, 20);
+        let generators: Vec<Box<dyn MapGenerator>> = vec![
+            Box::<DrunkardGenerator>::default(),
+            Box::<ConwayLifeGenerator>::default(),
+            Box::<RoomsGenerator>::default(),
+        ];
+        let mut maps = vec![];
+        for seed in 0..3 {
+            for generator in generators.iter() {
+                let mut rng = StdRng::seed_from_u64(seed);
+                let map = generator.gen(&mut rng, size);
+                let mut with_prefabs = map.clone();
+                PrefabStamper {
+                    prefabs: prefabs(),
+                    count: 2,
+                }
+                .stamp(&mut rng, &mut with_prefabs);
+                maps.extend([map, with_prefabs]);
+            }
+        }
+        for prefab in prefabs() {
+            let mut map = EmptyGenerator {}.gen(&mut StdRng::seed_from_u64(0), size);
+            prefab.stamp(&mut map, IVec2::new(3, 3));
+            maps.push(map);
+        }
+        maps
+    }
+
+    fn see_through(map: &Map) -> Vec<IVec2> {
+        map.enumerate()
+            .filter(|(_, t)| !t.blocks_sight())
+            .map(|(pt, _)| pt)
+            .collect()
+    }
+
+    fn fov_of_every_tile(map: &Map, options: FovOptions) -> Vec<(IVec2, BitGrid)> {
+        see_through(map)
+            .into_iter()
+            .map(|pt| (pt, options.compute(map, pt, RADIUS)))
+            .collect()
+    }
+
+    #[test]
+    fn shadowcasting_is_symmetric() {
+        for map in maps() {
+            let fovs = fov_of_every_tile(&map, FovOptions::default());
+            let index: std::collections::HashMap<_, _> =
+                fovs.iter().map(|(pt, fov)| (*pt, fov)).collect();
+            for (a, fov_a) in fovs.iter() {
+                for b in fov_a.iter().filter(|b| !map[*b].blocks_sight()) {
+                    assert!(
+                        index[&b].contains(*a),
+                        "{} sees {} but not back\n{}",
+                        a,
+                        b,
+                        map
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shadowcasting_sees_every_tile_in_clear_line() {
+        for map in maps() {
+            for (origin, fov) in fov_of_every_tile(&map, FovOptions::default()) {
+                for pt in see_through(&map) {
+                    let d = pt - origin;
+                    let is_clear = Supercover::new((origin.x, origin.y), (pt.x, pt.y))
+                        .all(|(x, y)| !map[IVec2::new(x, y)].blocks_sight());
+                    if d.x * d.x + d.y * d.y <= RADIUS * RADIUS && is_clear {
+                        assert!(fov.contains(pt), "{} does not see {}\n{}", origin, pt, map);
+                    }
+                }
+            }
+        }
+    }
+
+    /// field of view before shadowcasting (walls were the only sight blocking tiles)
+    fn ray_cast_old(map: &Map, pt: IVec2, radius: i32) -> BitGrid {
+        let mut fov = BitGrid::new(map.size());
+        for (xo, yo) in BresenhamCircle::new(pt.x, pt.y, radius) {
+            for vpt in Supercover::new((pt.x, pt.y), (xo, yo))
+                .map(|(x, y)| IVec2::new(x, y))
+                .filter(|p| map.is_in_bounds(*p))
+            {
+                if map[vpt] == Tile::Wall {
+                    fov.insert(vpt);
+                    break;
+                }
+                fov.insert(vpt);
+            }
+        }
+        fov
+    }
+
+    #[test]
+    fn ray_casting_matches_old_output() {
+        let options = FovOptions {
+            algorithm: FovAlgorithm::RayCasting,
+            walls: WallLighting::All,
+        };
+        for map in maps()
+            .into_iter()
+            .filter(|m| m.iter().all(|t| !t.blocks_sight() || *t == Tile::Wall))
+        {
+            for (origin, fov) in fov_of_every_tile(&map, options) {
+                let old = ray_cast_old(&map, origin, RADIUS);
+                assert_eq!(
+                    fov.symmetric_difference(&old).len(),
+                    0,
+                    "{}\n{}",
+                    origin,
+                    map
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn unlit_walls_are_next_to_visible_floor() {
+        let options = FovOptions {
+            walls: WallLighting::NextToVisibleFloor,
+            ..Default::default()
+        };
+        for map in maps() {
+            for (origin, fov) in fov_of_every_tile(&map, options) {
+                let all = FovOptions::default().compute(&map, origin, RADIUS);
+                for pt in all.iter() {
+                    let lit = !map[pt].blocks_sight()
+                        || Map::get_neighbor_deltas()
+                            .iter()
+                            .map(|d| pt + *d)
+                            .any(|nb| all.contains(nb) && !map[nb].blocks_sight());
+                    assert_eq!(fov.contains(pt), lit, "{} {}\n{}", origin, pt, map);
+                }
+            }
+        }
+    }
+}
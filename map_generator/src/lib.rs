@@ -1,26 +1,56 @@
+mod bit_grid;
+mod bsp;
+mod corridors;
+mod dijkstra;
 mod drunkard;
 mod empty;
+mod fov;
 mod life;
+mod light;
 mod map;
+mod path;
+mod pipeline;
+mod prefab;
 mod rect;
+mod regions;
 mod rooms;
+mod stats;
+mod wfc;
 
 mod prelude {
     pub use super::map::Map;
     pub use super::map::Tile;
     pub use super::rect::Rect;
     pub use super::MapGenerator;
+    pub use super::MapModifier;
     pub use glam::IVec2;
     pub use line_drawing::WalkGrid;
     pub use rand::prelude::*;
+    pub use serde::{Deserialize, Serialize};
 }
 
+pub use bit_grid::BitGrid;
+pub use bsp::BspGenerator;
+pub use corridors::{CorridorRoute, Corridors, RoomConnection};
+pub use dijkstra::DijkstraMap;
+pub use dijkstra::Neighborhood;
 pub use drunkard::DrunkardGenerator;
 pub use empty::EmptyGenerator;
-pub use life::ConwayLifeGenerator;
+pub use fov::{FovAlgorithm, FovOptions, WallLighting};
+pub use life::{CellularPass, CellularRule, ConwayLifeGenerator};
+pub use light::{Light, LightMap, WallTorches};
 pub use map::Map;
-pub use map::Tile;
+pub use map::{DoorState, MapParseError, Tile};
+pub use path::a_star;
+pub use pipeline::MapMask;
+pub use pipeline::MapPipeline;
+pub use prefab::{Prefab, PrefabPlacement, PrefabStamper};
+pub use rect::Rect;
+pub use regions::EnsureConnected;
+pub use regions::MapRegions;
 pub use rooms::RoomsGenerator;
+pub use stats::{GeneratorReport, MapStats};
+pub use wfc::WfcGenerator;
 
 use prelude::*;
 
@@ -28,12 +58,26 @@ pub trait MapGenerator {
     fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map;
 }
 
-// TODO: implement possibility to do map generation composition (mix multiple generators)
-// TODO: implement space straversal and room detector
-// TODO: dijkstra plotting to determine distinct not connected rooms
-// TODO: dijkstra plotting to place enemies, players at positions relative to wall sides
+impl<G: MapGenerator + ?Sized> MapGenerator for Box<G> {
+    fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
+        (**self).gen(rng, size)
+    }
+}
+
+/// Post processing pass applied on top of an already generated [`Map`].
+pub trait MapModifier {
+    fn modify(&self, rng: &mut StdRng, map: &mut Map);
+}
+
+impl<M: MapModifier + ?Sized> MapModifier for Box<M> {
+    fn modify(&self, rng: &mut StdRng, map: &mut Map) {
+        (**self).modify(rng, map)
+    }
+}
+
 // TODO: dijkstra plotting to identify 1 tile wide tunnels
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RandomMapGenerator {}
 
 impl MapGenerator for RandomMapGenerator {
@@ -1,44 +1,166 @@
 use super::prelude::*;
+use std::fmt::Display;
+use std::str::FromStr;
 
-const DEFAULT_ITER_COUNT: usize = 9;
+const DEFAULT_FILL_RATIO: f32 = 0.55;
+const DEFAULT_ITER_COUNT: usize = 10;
 
+/// Cellular automata rule in B/S notation (`"B5678/S45678"`). Walls are the living cells.
+/// A floor tile turns into wall when its wall neighbor count (out of 8) is listed after `B` (birth),
+/// a wall stays wall when its count is listed after `S` (survival), otherwise the tile becomes floor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct CellularRule {
+    pub birth: [bool; 9],
+    pub survival: [bool; 9],
+}
+
+impl CellularRule {
+    /// wall neighbor counts in 1..=4 make a wall, the rule of the original Conway life generator
+    pub const RUGGED: CellularRule = CellularRule {
+        birth: [false, true, true, true, true, false, false, false, false],
+        survival: [false, true, true, true, true, false, false, false, false],
+    };
+
+    /// whether the tile is a wall in the next iteration
+    pub fn next(&self, is_wall: bool, wall_neighbors: usize) -> bool {
+        if is_wall {
+            self.survival[wall_neighbors]
+        } else {
+            self.birth[wall_neighbors]
+        }
+    }
+}
+
+impl Default for CellularRule {
+    fn default() -> Self {
+        Self::RUGGED
+    }
+}
+
+impl FromStr for CellularRule {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (birth, survival) = s
+            .trim()
+            .split_once('/')
+            .ok_or_else(|| format!("rule {} is not in B/S notation (B678/S345678)", s))?;
+        let parse = |part: &str, prefix: char| {
+            let mut counts = [false; 9];
+            let digits = part
+                .strip_prefix(prefix)
+                .or_else(|| part.strip_prefix(prefix.to_ascii_lowercase()))
+                .ok_or_else(|| format!("rule part {} should start with {}", part, prefix))?;
+            for c in digits.chars() {
+                match c.to_digit(10) {
+                    Some(d) if d < 9 => counts[d as usize] = true,
+                    _ => {
+                        return Err(format!(
+                            "rule part {} has invalid neighbor count {}",
+                            part, c
+                        ))
+                    }
+                }
+            }
+            Ok(counts)
+        };
+        Ok(Self {
+            birth: parse(birth, 'B')?,
+            survival: parse(survival, 'S')?,
+        })
+    }
+}
+
+impl TryFrom<String> for CellularRule {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        value.parse()
+    }
+}
+
+impl From<CellularRule> for String {
+    fn from(rule: CellularRule) -> Self {
+        rule.to_string()
+    }
+}
+
+impl Display for CellularRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let counts = |list: &[bool; 9]| -> String {
+            (0..9)
+                .filter(|i| list[*i])
+                .map(|i| char::from(b'0' + i as u8))
+                .collect()
+        };
+        write!(f, "B{}/S{}", counts(&self.birth), counts(&self.survival))
+    }
+}
+
+/// `iter_count` iterations of the same rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CellularPass {
+    pub rule: CellularRule,
+    pub iter_count: usize,
+}
+
+/// Cellular automata cave generator. Starts from random noise (`fill_ratio` of walls)
+/// and runs the passes in order, e.g. a few rough passes opening caverns followed by smoothing passes.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct ConwayLifeGenerator {
-    iter_count: usize,
+    /// chance of a tile to start as wall
+    pub fill_ratio: f32,
+    pub passes: Vec<CellularPass>,
 }
 
 impl Default for ConwayLifeGenerator {
     fn default() -> Self {
         Self {
-            iter_count: DEFAULT_ITER_COUNT,
+            fill_ratio: DEFAULT_FILL_RATIO,
+            passes: vec![CellularPass {
+                rule: CellularRule::RUGGED,
+                iter_count: DEFAULT_ITER_COUNT,
+            }],
         }
     }
 }
 
 impl MapGenerator for ConwayLifeGenerator {
     fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
-        let mut map = Map::random_noise(size, rng);
-        for _ in 0..=self.iter_count {
-            iteration(&mut map);
+        let mut map = Map::random_noise(size, self.fill_ratio, rng);
+        self.modify(rng, &mut map);
+        map
+    }
+}
+
+/// Runs the cellular automata passes over an existing map (smoothing pass).
+impl MapModifier for ConwayLifeGenerator {
+    fn modify(&self, _rng: &mut StdRng, map: &mut Map) {
+        for pass in self.passes.iter() {
+            for _ in 0..pass.iter_count {
+                iteration(map, &pass.rule);
+            }
         }
         for p in map.get_edge() {
             let t = &mut map[p];
             *t = Tile::Wall;
         }
-        map
     }
 }
 
-fn iteration(map: &mut Map) {
+fn iteration(map: &mut Map, rule: &CellularRule) {
     let map_clone = map.clone();
     for y in 1..map_clone.size().y - 1 {
         for x in 1..map_clone.size().x - 1 {
             let pt = IVec2::new(x, y);
             let neighbors = count_neighbors(&map_clone, pt);
             let t = &mut map[pt];
-            *t = if neighbors > 4 || neighbors == 0 {
-                Tile::Floor
-            } else {
+            *t = if rule.next(map_clone[pt] == Tile::Wall, neighbors) {
                 Tile::Wall
+            } else {
+                Tile::Floor
             };
         }
     }
@@ -51,3 +173,72 @@ fn count_neighbors(map: &Map, pt: IVec2) -> usize {
         .filter(|nb| map.is_in_bounds(**nb) && map[**nb] == Tile::Wall)
         .count()
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rule_parses_and_displays_b_s_notation() {
+        let rule: CellularRule = "B5678/S45678".parse().unwrap();
+        assert_eq!(
+            rule.birth,
+            [false, false, false, false, false, true, true, true, true]
+        );
+        assert_eq!(
+            rule.survival,
+            [false, false, false, false, true, true, true, true, true]
+        );
+        assert_eq!(rule.to_string(), "B5678/S45678");
+        assert_eq!(
+            " b1234/s1234 ".parse::<CellularRule>(),
+            Ok(CellularRule::RUGGED)
+        );
+        assert_eq!(CellularRule::RUGGED.to_string(), "B1234/S1234");
+        assert_eq!("B/S".parse::<CellularRule>().unwrap().to_string(), "B/S");
+    }
+
+    #[test]
+    fn rule_parse_errors() {
+        for text in ["B5678", "S45/B678", "B59/S4", "Bx/S4", ""] {
+            assert!(text.parse::<CellularRule>().is_err(), "{}", text);
+        }
+    }
+
+    #[test]
+    fn rule_serializes_as_text() {
+        let pass = CellularPass {
+            rule: "B678/S345678".parse().unwrap(),
+            iter_count: 3,
+        };
+        let text = ron::to_string(&pass).unwrap();
+        assert_eq!(text, r#"(rule:"B678/S345678",iter_count:3)"#);
+        let read: CellularPass = ron::from_str(&text).unwrap();
+        assert_eq!(read.rule, pass.rule);
+        assert!(ron::from_str::<CellularPass>(r#"(rule:"B9/S1",iter_count:3)"#).is_err());
+    }
+
+    #[test]
+    fn rule_next_follows_birth_and_survival() {
+        let rule: CellularRule = "B3/S23".parse().unwrap();
+        assert!(rule.next(false, 3));
+        assert!(!rule.next(false, 2));
+        assert!(rule.next(true, 2));
+        assert!(!rule.next(true, 4));
+    }
+
+    #[test]
+    fn pass_applies_the_rule_to_inner_tiles() {
+        let mut map: Map = "#####\n#...#\n#.#.#\n#...#\n#####\n".parse().unwrap();
+        ConwayLifeGenerator {
+            fill_ratio: 0.,
+            passes: vec![CellularPass {
+                rule: "B5678/S45678".parse().unwrap(),
+                iter_count: 1,
+            }],
+        }
+        .modify(&mut StdRng::seed_from_u64(1), &mut map);
+        // NOTE: the lonely wall dies, corners (6 wall neighbors) are born
+        assert_eq!(map.to_string(), "#####\n##.##\n#...#\n##.##\n#####\n");
+    }
+}
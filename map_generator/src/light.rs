@@ -0,0 +1,236 @@
+use super::prelude::*;
+use super::{BitGrid, FovOptions};
+
+/// Light given off from a single tile
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Light {
+    /// farthest lit tile
+    pub radius: i32,
+    /// light level at the source (up to 1.), fading out linearly towards the radius
+    pub intensity: f32,
+}
+
+/// Light level (0. dark, 1. fully lit) of every map tile.
+/// Light spreads where sight goes, levels of overlapping lights add up.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LightMap {
+    size: IVec2,
+    levels: Vec<f32>,
+}
+
+impl LightMap {
+    /// every tile starts at the `ambient` level
+    pub fn new(size: IVec2, ambient: f32) -> Self {
+        Self {
+            size,
+            levels: vec![ambient.clamp(0., 1.); (size.x.max(0) * size.y.max(0)) as usize],
+        }
+    }
+
+    pub fn size(&self) -> IVec2 {
+        self.size
+    }
+
+    fn is_in_bounds(&self, pt: IVec2) -> bool {
+        self.size.x > pt.x && self.size.y > pt.y && pt.x >= 0 && pt.y >= 0
+    }
+
+    /// light level of the tile. 0. outside of the map.
+    pub fn level(&self, pt: IVec2) -> f32 {
+        if !self.is_in_bounds(pt) {
+            return 0.;
+        }
+        self.levels[(pt.y * self.size.x + pt.x) as usize]
+    }
+
+    /// Lights tiles visible from `origin` (see [`FovOptions`]).
+    /// A sight blocking origin (torch on a wall) lights only what is visible from its floor neighbors.
+    pub fn add(&mut self, map: &Map, fov: &FovOptions, origin: IVec2, light: Light) {
+        if !map.is_in_bounds(origin) || light.radius < 0 {
+            return;
+        }
+        let lit = if map[origin].blocks_sight() {
+            let mut lit = BitGrid::new(map.size());
+            lit.insert(origin);
+            for nb in Map::get_wasd_neighbor_deltas()
+                .iter()
+                .map(|d| origin + *d)
+                .filter(|nb| map.is_in_bounds(*nb) && !map[*nb].blocks_sight())
+            {
+                lit.union_with(&fov.compute(map, nb, light.radius - 1));
+            }
+            lit
+        } else {
+            fov.compute(map, origin, light.radius)
+        };
+        for pt in lit.iter() {
+            if !self.is_in_bounds(pt) {
+                continue;
+            }
+            let distance = (pt - origin).as_vec2().length();
+            let level = light.intensity * (1. - distance / (light.radius + 1) as f32);
+            if level > 0. {
+                let idx = (pt.y * self.size.x + pt.x) as usize;
+                self.levels[idx] = (self.levels[idx] + level).min(1.);
+            }
+        }
+    }
+}
+
+/// Places torches on walls at least `spacing` tiles apart.
+/// Only walls facing a single floor tile (up, down, left or right) hold a torch,
+/// so a torch never lights both sides of a wall.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct WallTorches {
+    pub spacing: i32,
+}
+
+impl WallTorches {
+    pub fn place(&self, rng: &mut StdRng, map: &Map) -> Vec<IVec2> {
+        if self.spacing <= 0 {
+            return vec![];
+        }
+        let mut candidates: Vec<_> = map
+            .enumerate()
+            .filter(|(pt, tile)| {
+                **tile == Tile::Wall
+                    && Map::get_wasd_neighbor_deltas()
+                        .iter()
+                        .map(|d| *pt + *d)
+                        .filter(|nb| map.is_in_bounds(*nb) && map[*nb] == Tile::Floor)
+                        .count()
+                        == 1
+            })
+            .map(|(pt, _)| pt)
+            .collect();
+        candidates.shuffle(rng);
+        let spacing_squared = self.spacing * self.spacing;
+        let mut torches: Vec<IVec2> = vec![];
+        for pt in candidates {
+            let is_spaced = torches.iter().all(|t| {
+                let d = *t - pt;
+                d.x * d.x + d.y * d.y >= spacing_squared
+            });
+            if is_spaced {
+                torches.push(pt);
+            }
+        }
+        torches
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::RoomsGenerator;
+
+    fn lit(map: &Map, origin: IVec2, light: Light) -> LightMap {
+        let mut lights = LightMap::new(map.size(), 0.);
+        lights.add(map, &FovOptions::default(), origin, light);
+        lights
+    }
+
+    #[test]
+    fn ambient_level_is_clamped() {
+        let lights = LightMap::new(IVec2::new(2, 2), 1.5);
+        assert_eq!(lights.level(IVec2::new(1, 1)), 1.);
+        assert_eq!(lights.level(IVec2::new(2, 1)), 0.);
+        assert_eq!(LightMap::new(IVec2::new(2, 2), -1.).level(IVec2::ZERO), 0.);
+    }
+
+    #[test]
+    fn light_fades_out_towards_the_radius() {
+        let map: Map = "#########\n#.......#\n#.......#\n#.......#\n#########\n"
+            .parse()
+            .unwrap();
+        let origin = IVec2::new(2, 2);
+        let lights = lit(
+            &map,
+            origin,
+            Light {
+                radius: 3,
+                intensity: 0.8,
+            },
+        );
+        assert_eq!(lights.level(origin), 0.8);
+        let levels: Vec<_> = (3..=6)
+            .map(|x| lights.level(IVec2::new(x, origin.y)))
+            .collect();
+        assert!(levels.windows(2).all(|w| w[0] > w[1]), "{:?}", levels);
+        assert_eq!(levels[3], 0., "beyond the radius");
+    }
+
+    #[test]
+    fn walls_cast_shadows() {
+        let map: Map = "#######\n#..#..#\n#..#..#\n#######\n".parse().unwrap();
+        let lights = lit(
+            &map,
+            IVec2::new(1, 1),
+            Light {
+                radius: 6,
+                intensity: 1.,
+            },
+        );
+        assert!(
+            lights.level(IVec2::new(3, 1)) > 0.,
+            "the wall itself is lit"
+        );
+        for pt in [IVec2::new(4, 1), IVec2::new(5, 2)] {
+            assert_eq!(lights.level(pt), 0.);
+        }
+    }
+
+    #[test]
+    fn overlapping_lights_add_up_to_full() {
+        let map: Map = "#####\n#...#\n#####\n".parse().unwrap();
+        let mut lights = LightMap::new(map.size(), 0.25);
+        let light = Light {
+            radius: 2,
+            intensity: 0.5,
+        };
+        lights.add(&map, &FovOptions::default(), IVec2::new(1, 1), light);
+        assert_eq!(lights.level(IVec2::new(1, 1)), 0.75);
+        lights.add(&map, &FovOptions::default(), IVec2::new(1, 1), light);
+        assert_eq!(lights.level(IVec2::new(1, 1)), 1.);
+    }
+
+    #[test]
+    fn wall_torch_lights_its_floor_side_only() {
+        let map: Map = "#######\n#..#..#\n#..#..#\n#######\n".parse().unwrap();
+        // NOTE: the torch hangs on the right map edge, facing the right room only
+        let torch = IVec2::new(6, 2);
+        let lights = lit(
+            &map,
+            torch,
+            Light {
+                radius: 3,
+                intensity: 1.,
+            },
+        );
+        assert_eq!(lights.level(torch), 1.);
+        assert!(lights.level(IVec2::new(4, 1)) > 0.);
+        assert_eq!(lights.level(IVec2::new(2, 2)), 0.);
+    }
+
+    #[test]
+    fn torches_are_spaced_on_walls_facing_one_floor_tile() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let map = RoomsGenerator::default().gen(&mut rng, IVec2::new(48, 24));
+        assert!(WallTorches { spacing: 0 }.place(&mut rng, &map).is_empty());
+
+        let torches = WallTorches { spacing: 6 }.place(&mut rng, &map);
+        assert!(!torches.is_empty());
+        for (i, torch) in torches.iter().enumerate() {
+            assert_eq!(map[*torch], Tile::Wall);
+            let floor_sides = Map::get_wasd_neighbor_deltas()
+                .iter()
+                .filter(|d| map.is_in_bounds(*torch + **d) && map[*torch + **d] == Tile::Floor)
+                .count();
+            assert_eq!(floor_sides, 1);
+            for other in torches[i + 1..].iter() {
+                let d = *other - *torch;
+                assert!(d.x * d.x + d.y * d.y >= 36);
+            }
+        }
+    }
+}
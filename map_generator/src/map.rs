@@ -1,40 +1,257 @@
 use glam::IVec2;
 use rand::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::fmt::Display;
 use std::ops::{Index, IndexMut};
 use std::slice::Iter;
+use std::str::FromStr;
 
 /// Enum describing a map tile
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
 pub enum Tile {
     Wall,
     Floor,
+    Door(DoorState),
+    ShallowWater,
+    DeepWater,
+    Lava,
+    Rubble,
+    StairsUp,
+    StairsDown,
+}
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+pub enum DoorState {
+    Open,
+    /// passable, opens when entered
+    Closed,
+    Locked,
+}
+
+const LAVA_DAMAGE: u16 = 8;
+
+impl Tile {
+    pub fn is_passable(&self) -> bool {
+        self.movement_cost().is_some()
+    }
+
+    pub fn blocks_sight(&self) -> bool {
+        matches!(
+            self,
+            Tile::Wall | Tile::Door(DoorState::Closed) | Tile::Door(DoorState::Locked)
+        )
+    }
+
+    /// cost of entering the tile relative to a floor tile. `None` if the tile is impassable
+    pub fn movement_cost(&self) -> Option<i32> {
+        match self {
+            Tile::Wall | Tile::DeepWater | Tile::Door(DoorState::Locked) => None,
+            Tile::Door(DoorState::Closed) | Tile::ShallowWater | Tile::Rubble => Some(2),
+            Tile::Floor
+            | Tile::Door(DoorState::Open)
+            | Tile::Lava
+            | Tile::StairsUp
+            | Tile::StairsDown => Some(1),
+        }
+    }
+
+    /// hit points lost when stepping onto the tile
+    pub fn damage_on_enter(&self) -> u16 {
+        match self {
+            Tile::Lava => LAVA_DAMAGE,
+            _ => 0,
+        }
+    }
+    /// ascii character representing the tile
+    pub fn glyph(&self) -> char {
+        match self {
+            Tile::Wall => '#',
+            Tile::Floor => '.',
+            Tile::Door(DoorState::Open) => '/',
+            Tile::Door(DoorState::Closed) => '+',
+            Tile::Door(DoorState::Locked) => '=',
+            Tile::ShallowWater => '~',
+            Tile::DeepWater => 'W',
+            Tile::Lava => '&',
+            Tile::Rubble => ':',
+            Tile::StairsUp => '<',
+            Tile::StairsDown => '>',
+        }
+    }
+
+    /// inverse of [`Tile::glyph`]
+    pub fn from_glyph(glyph: char) -> Option<Tile> {
+        match glyph {
+            '#' => Some(Tile::Wall),
+            '.' => Some(Tile::Floor),
+            '/' => Some(Tile::Door(DoorState::Open)),
+            '+' => Some(Tile::Door(DoorState::Closed)),
+            '=' => Some(Tile::Door(DoorState::Locked)),
+            '~' => Some(Tile::ShallowWater),
+            'W' => Some(Tile::DeepWater),
+            '&' => Some(Tile::Lava),
+            ':' => Some(Tile::Rubble),
+            '<' => Some(Tile::StairsUp),
+            '>' => Some(Tile::StairsDown),
+            _ => None,
+        }
+    }
 }
 
 // TODO: inject Resource? have a trait for map instead
-/// Flat tile map of tiles
-#[derive(Debug, Clone)]
+/// Flat tile map of tiles.
+/// Round-trips through plain text ([`Display`] and [`FromStr`]) using [`Tile::glyph`] characters,
+/// one line per row starting with the top one. Serde uses the same rows (`(rows: ["###", "#.#", "###"])`).
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "MapRows", into = "MapRows")]
 pub struct Map {
     size: IVec2,
     tiles: Vec<Tile>,
 }
 
+/// Reasons text can not be read as a [`Map`].
+/// Rows are text lines counting from the top, columns are characters counting from the left (both start with 0).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MapParseError {
+    /// no rows
+    Empty,
+    /// row is of a different width than the first one
+    RaggedRow {
+        row: usize,
+        width: usize,
+        expected: usize,
+    },
+    UnknownGlyph {
+        glyph: char,
+        row: usize,
+        column: usize,
+    },
+}
+
+impl MapParseError {
+    /// same error with the row replaced
+    pub(crate) fn with_row(self, row: impl Fn(usize) -> usize) -> Self {
+        match self {
+            MapParseError::Empty => MapParseError::Empty,
+            MapParseError::RaggedRow {
+                row: r,
+                width,
+                expected,
+            } => MapParseError::RaggedRow {
+                row: row(r),
+                width,
+                expected,
+            },
+            MapParseError::UnknownGlyph {
+                glyph,
+                row: r,
+                column,
+            } => MapParseError::UnknownGlyph {
+                glyph,
+                row: row(r),
+                column,
+            },
+        }
+    }
+}
+
+impl Display for MapParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MapParseError::Empty => write!(f, "map has no rows"),
+            MapParseError::RaggedRow {
+                row,
+                width,
+                expected,
+            } => write!(
+                f,
+                "map row {} is {} tiles wide, expected {}",
+                row, width, expected
+            ),
+            MapParseError::UnknownGlyph { glyph, row, column } => write!(
+                f,
+                "unknown tile glyph '{}' at row {} column {}",
+                glyph, row, column
+            ),
+        }
+    }
+}
+
+impl std::error::Error for MapParseError {}
+
 impl Map {
-    pub(crate) fn filled_with(size: IVec2, tile: Tile) -> Self {
+    pub fn filled_with(size: IVec2, tile: Tile) -> Self {
         Self {
             size,
             tiles: vec![tile; (size.x * size.y) as usize],
         }
     }
 
-    pub(crate) fn random_noise(size: IVec2, rng: &mut StdRng) -> Self {
+    /// reads rows of [`Tile::glyph`] characters. first row is the top of the map.
+    pub fn from_rows<S: AsRef<str>>(rows: &[S]) -> Result<Self, MapParseError> {
+        let expected = rows
+            .first()
+            .ok_or(MapParseError::Empty)?
+            .as_ref()
+            .chars()
+            .count();
+        if expected == 0 {
+            return Err(MapParseError::Empty);
+        }
+        let size = IVec2::new(expected as i32, rows.len() as i32);
+        let mut map = Map::filled_with(size, Tile::Wall);
+        for (row, line) in rows.iter().enumerate() {
+            let width = line.as_ref().chars().count();
+            if width != expected {
+                return Err(MapParseError::RaggedRow {
+                    row,
+                    width,
+                    expected,
+                });
+            }
+            for (column, glyph) in line.as_ref().chars().enumerate() {
+                let pt = IVec2::new(column as i32, size.y - 1 - row as i32);
+                map[pt] = Tile::from_glyph(glyph).ok_or(MapParseError::UnknownGlyph {
+                    glyph,
+                    row,
+                    column,
+                })?;
+            }
+        }
+        Ok(map)
+    }
+
+    /// rows of [`Tile::glyph`] characters. first row is the top of the map.
+    pub fn to_rows(&self) -> Vec<String> {
+        (0..self.size.y)
+            .rev()
+            .map(|y| self.get_slice(y).iter().map(|t| t.glyph()).collect())
+            .collect()
+    }
+
+    /// every tile is a wall with `wall_ratio` chance, floor otherwise
+    pub(crate) fn random_noise(size: IVec2, wall_ratio: f32, rng: &mut StdRng) -> Self {
+        let wall_ratio = wall_ratio.clamp(0., 1.) as f64;
         let mut tiles = Vec::new();
         for _ in 0..size.x * size.y {
-            let roll = rng.gen_range(0..=100);
-            tiles.push(if roll > 55 { Tile::Floor } else { Tile::Wall })
+            tiles.push(if rng.gen_bool(wall_ratio) {
+                Tile::Wall
+            } else {
+                Tile::Floor
+            })
         }
         Self { size, tiles }
     }
 
+    /// copies tiles from `other` where the corresponding mask flag is set
+    pub(crate) fn blend(&mut self, other: &Map, mask: &[bool]) {
+        for ((tile, other), is_masked) in self.tiles.iter_mut().zip(other.iter()).zip(mask) {
+            if *is_masked {
+                *tile = *other;
+            }
+        }
+    }
+
     pub fn is_in_bounds(&self, pt: IVec2) -> bool {
         self.size.x > pt.x && self.size.y > pt.y && pt.x >= 0 && pt.y >= 0
     }
@@ -43,6 +260,40 @@ impl Map {
         pt.x == 0 || pt.y == 0 || pt.x == self.size.x - 1 || pt.y == self.size.y - 1
     }
 
+    /// passable tile with a single passable neighbor (up, down, left, right)
+    pub fn is_dead_end(&self, pt: IVec2) -> bool {
+        self[pt].is_passable() && self.count_wasd_passable(pt) == 1
+    }
+
+    /// passable tile inside a room touching walls on two perpendicular sides.
+    /// Corridor bends do not count since their diagonal neighbor is a wall.
+    pub fn is_corner(&self, pt: IVec2) -> bool {
+        if !self[pt].is_passable() || self.count_wasd_passable(pt) != 2 {
+            return false;
+        }
+        let is_floor = |p: IVec2| self.is_in_bounds(p) && self[p].is_passable();
+        [
+            IVec2::new(1, 1),
+            IVec2::new(1, -1),
+            IVec2::new(-1, 1),
+            IVec2::new(-1, -1),
+        ]
+        .iter()
+        .any(|d| {
+            is_floor(pt + IVec2::new(d.x, 0))
+                && is_floor(pt + IVec2::new(0, d.y))
+                && is_floor(pt + *d)
+        })
+    }
+
+    fn count_wasd_passable(&self, pt: IVec2) -> usize {
+        Map::get_wasd_neighbor_deltas()
+            .iter()
+            .map(|d| pt + *d)
+            .filter(|nb| self.is_in_bounds(*nb) && self[*nb].is_passable())
+            .count()
+    }
+
     /// itterates over underlying tiles vector
     pub fn iter(&self) -> Iter<Tile> {
         self.tiles.iter()
@@ -76,10 +327,10 @@ impl Map {
     }
 
     /// zero based indexing
-    fn get_index(&self, pt: IVec2) -> usize {
+    pub(crate) fn get_index(&self, pt: IVec2) -> usize {
         (pt.y * (self.size.x) + pt.x) as usize
     }
-    fn get_point(&self, idx: usize) -> IVec2 {
+    pub(crate) fn get_point(&self, idx: usize) -> IVec2 {
         IVec2::new(
             (idx % self.size.x as usize) as i32,
             (idx / self.size.x as usize) as i32,
@@ -134,3 +385,128 @@ impl IndexMut<IVec2> for Map {
         &mut self.tiles[idx]
     }
 }
+
+impl Display for Map {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for row in self.to_rows() {
+            writeln!(f, "{}", row)?;
+        }
+        Ok(())
+    }
+}
+
+/// Reads text written by [`Display`]. Empty lines and trailing whitespace are ignored.
+impl FromStr for Map {
+    type Err = MapParseError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (lines, rows): (Vec<_>, Vec<_>) = s
+            .lines()
+            .map(|l| l.trim_end())
+            .enumerate()
+            .filter(|(_, l)| !l.is_empty())
+            .unzip();
+        // NOTE: errors point at lines of the text, empty ones included
+        Map::from_rows(&rows).map_err(|err| err.with_row(|row| lines[row]))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct MapRows {
+    rows: Vec<String>,
+}
+
+impl TryFrom<MapRows> for Map {
+    type Error = MapParseError;
+
+    fn try_from(value: MapRows) -> Result<Self, Self::Error> {
+        Map::from_rows(&value.rows)
+    }
+}
+
+impl From<Map> for MapRows {
+    fn from(map: Map) -> Self {
+        Self {
+            rows: map.to_rows(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "#####\n#.+/#\n#~W&#\n#:<>#\n#=..#\n#####\n";
+
+    #[test]
+    fn rows_round_trip() {
+        let rows = ["###", "#.>", "###"];
+        let map = Map::from_rows(&rows).unwrap();
+        assert_eq!(map.size(), IVec2::new(3, 3));
+        // NOTE: first row is the top of the map
+        assert_eq!(map[IVec2::new(2, 1)], Tile::StairsDown);
+        assert_eq!(map.to_rows(), rows);
+    }
+
+    #[test]
+    fn text_round_trip() {
+        let map: Map = TEXT.parse().unwrap();
+        assert_eq!(map.to_string(), TEXT);
+        assert_eq!(map[IVec2::new(1, 1)], Tile::Door(DoorState::Locked));
+        assert_eq!(map[IVec2::new(2, 4)], Tile::Door(DoorState::Closed));
+        let padded = format!("\n{}\n", TEXT.replace('\n', "  \n"));
+        assert_eq!(padded.parse::<Map>(), Ok(map));
+    }
+
+    #[test]
+    fn every_glyph_round_trips() {
+        let map: Map = TEXT.parse().unwrap();
+        for tile in map.iter() {
+            assert_eq!(Tile::from_glyph(tile.glyph()), Some(*tile));
+        }
+    }
+
+    #[test]
+    fn ron_round_trip() {
+        let map = Map::from_rows(&["###", "#.#", "###"]).unwrap();
+        let text = ron::to_string(&map).unwrap();
+        assert_eq!(text, r####"(rows:["###","#.#","###"])"####);
+        assert_eq!(ron::from_str::<Map>(&text).unwrap(), map);
+        let map: Map = TEXT.parse().unwrap();
+        assert_eq!(
+            ron::from_str::<Map>(&ron::to_string(&map).unwrap()).unwrap(),
+            map
+        );
+        assert!(ron::from_str::<Map>(r####"(rows:["###","#.","###"])"####).is_err());
+    }
+
+    #[test]
+    fn parse_errors_point_at_text() {
+        assert_eq!("".parse::<Map>(), Err(MapParseError::Empty));
+        assert_eq!(Map::from_rows::<&str>(&[]), Err(MapParseError::Empty));
+        assert_eq!(
+            "###\n#.\n###\n".parse::<Map>(),
+            Err(MapParseError::RaggedRow {
+                row: 1,
+                width: 2,
+                expected: 3
+            })
+        );
+        assert_eq!(
+            "###\n\n#.?\n###\n".parse::<Map>(),
+            Err(MapParseError::UnknownGlyph {
+                glyph: '?',
+                row: 2,
+                column: 2
+            })
+        );
+        assert_eq!(
+            Map::from_rows(&["##", "#x"]),
+            Err(MapParseError::UnknownGlyph {
+                glyph: 'x',
+                row: 1,
+                column: 1
+            })
+        );
+    }
+}
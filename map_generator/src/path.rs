@@ -0,0 +1,169 @@
+use super::prelude::*;
+use super::Neighborhood;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+
+/// Cheapest path from `start` to `goal` (both included) using A* search.
+/// `cost` returns cost to enter the tile or `None` if the tile is impassable.
+/// Costs should be at least 1, otherwise the path found might not be the cheapest one.
+pub fn a_star(
+    size: IVec2,
+    start: IVec2,
+    goal: IVec2,
+    neighborhood: Neighborhood,
+    cost: impl Fn(IVec2) -> Option<i32>,
+) -> Option<Vec<IVec2>> {
+    let in_bounds = |pt: IVec2| pt.x >= 0 && pt.y >= 0 && pt.x < size.x && pt.y < size.y;
+    if !in_bounds(start) || !in_bounds(goal) {
+        return None;
+    }
+    let get_index = |pt: IVec2| (pt.y * size.x + pt.x) as usize;
+    let heuristic = |pt: IVec2| {
+        let d = (goal - pt).abs();
+        match neighborhood {
+            Neighborhood::Four => d.x + d.y,
+            Neighborhood::Eight => i32::max(d.x, d.y),
+        }
+    };
+    let mut distances: Vec<Option<i32>> = vec![None; (size.x * size.y) as usize];
+    let mut came_from: Vec<Option<IVec2>> = vec![None; (size.x * size.y) as usize];
+    let mut frontier = BinaryHeap::new();
+    distances[get_index(start)] = Some(0);
+    frontier.push((Reverse(heuristic(start)), Reverse(0), start.x, start.y));
+    let deltas = neighborhood.deltas();
+    while let Some((_, Reverse(distance), x, y)) = frontier.pop() {
+        let pt = IVec2::new(x, y);
+        if pt == goal {
+            let mut path = vec![pt];
+            let mut prev = came_from[get_index(pt)];
+            while let Some(p) = prev {
+                path.push(p);
+                prev = came_from[get_index(p)];
+            }
+            path.reverse();
+            return Some(path);
+        }
+        if distances[get_index(pt)].is_some_and(|d| d < distance) {
+            continue;
+        }
+        for nb in deltas.iter().map(|d| pt + *d) {
+            if !in_bounds(nb) {
+                continue;
+            }
+            if let Some(step) = cost(nb) {
+                let next = distance + step;
+                let idx = get_index(nb);
+                if distances[idx].is_none_or(|d| next < d) {
+                    distances[idx] = Some(next);
+                    came_from[idx] = Some(pt);
+                    frontier.push((Reverse(next + heuristic(nb)), Reverse(next), nb.x, nb.y));
+                }
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{DijkstraMap, DrunkardGenerator};
+
+    fn path_on(map: &Map, start: IVec2, goal: IVec2, nh: Neighborhood) -> Option<Vec<IVec2>> {
+        a_star(map.size(), start, goal, nh, |pt| map[pt].movement_cost())
+    }
+
+    /// cost of walking the path (the start is not entered)
+    fn cost_of(map: &Map, path: &[IVec2]) -> i32 {
+        path.iter()
+            .skip(1)
+            .map(|pt| map[*pt].movement_cost().unwrap())
+            .sum()
+    }
+
+    fn is_connected(path: &[IVec2], nh: Neighborhood) -> bool {
+        let deltas = nh.deltas();
+        path.windows(2).all(|w| deltas.contains(&(w[1] - w[0])))
+    }
+
+    #[test]
+    fn path_goes_around_walls() {
+        let map: Map = "#######\n#..#..#\n#..#..#\n#.....#\n#######\n"
+            .parse()
+            .unwrap();
+        let start = IVec2::new(1, 3);
+        let goal = IVec2::new(5, 3);
+        let path = path_on(&map, start, goal, Neighborhood::Four).unwrap();
+        assert_eq!(path.first(), Some(&start));
+        assert_eq!(path.last(), Some(&goal));
+        assert!(is_connected(&path, Neighborhood::Four));
+        assert!(path.iter().all(|pt| map[*pt].is_passable()));
+        assert_eq!(path.len(), 9);
+
+        let diagonal = path_on(&map, start, goal, Neighborhood::Eight).unwrap();
+        assert!(is_connected(&diagonal, Neighborhood::Eight));
+        assert_eq!(diagonal.len(), 5);
+    }
+
+    #[test]
+    fn cheaper_tiles_are_preferred() {
+        // NOTE: rubble costs 2, the detour over floor is as long but cheaper
+        let map: Map = "#######\n#.....#\n#.:::.#\n#######\n".parse().unwrap();
+        let path = path_on(&map, IVec2::new(1, 1), IVec2::new(5, 1), Neighborhood::Four).unwrap();
+        assert_eq!(cost_of(&map, &path), 6);
+        assert!(path.iter().all(|pt| map[*pt] == Tile::Floor));
+    }
+
+    #[test]
+    fn no_path() {
+        let map: Map = "#####\n#.#.#\n#####\n".parse().unwrap();
+        let start = IVec2::new(1, 1);
+        assert_eq!(
+            path_on(&map, start, IVec2::new(3, 1), Neighborhood::Eight),
+            None
+        );
+        assert_eq!(
+            path_on(&map, start, IVec2::new(5, 1), Neighborhood::Four),
+            None
+        );
+        assert_eq!(
+            path_on(&map, IVec2::new(-1, 1), start, Neighborhood::Four),
+            None
+        );
+        assert_eq!(
+            path_on(&map, start, start, Neighborhood::Four),
+            Some(vec![start])
+        );
+    }
+
+    #[test]
+    fn path_cost_matches_dijkstra() {
+        for seed in 0..5 {
+            let map = DrunkardGenerator::default()
+                .gen(&mut StdRng::seed_from_u64(seed), IVec2::new(40, 24));
+            let floor: Vec<_> = map
+                .enumerate()
+                .filter(|(_, t)| t.is_passable())
+                .map(|(pt, _)| pt)
+                .collect();
+            let mut rng = StdRng::seed_from_u64(seed);
+            for nh in [Neighborhood::Four, Neighborhood::Eight] {
+                let start = *floor.choose(&mut rng).unwrap();
+                let distances = DijkstraMap::with_cost(map.size(), [(start, 0)], nh, |pt| {
+                    map[pt].movement_cost()
+                });
+                for goal in floor.choose_multiple(&mut rng, 20) {
+                    let path = path_on(&map, start, *goal, nh);
+                    match distances.get(*goal) {
+                        Some(distance) => {
+                            let path = path.unwrap();
+                            assert!(is_connected(&path, nh));
+                            assert_eq!(cost_of(&map, &path), distance, "{} -> {}", start, goal);
+                        }
+                        None => assert_eq!(path, None),
+                    }
+                }
+            }
+        }
+    }
+}
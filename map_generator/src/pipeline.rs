@@ -0,0 +1,203 @@
+use super::prelude::*;
+
+/// Selects the tiles a pipeline step is allowed to overwrite.
+#[derive(Debug, Default, Clone, Serialize, Deserialize)]
+pub enum MapMask {
+    /// every tile of the map
+    #[default]
+    All,
+    /// tiles inside the rectangle
+    Region(Rect),
+    /// tiles left of the `ratio` of the map width
+    Left(f32),
+    /// tiles below the `ratio` of the map height
+    Bottom(f32),
+    /// tiles inside of the ellipse centered on the map. `ratio` of 1. touches the map edges, 0. or less selects nothing
+    Center(f32),
+    /// every tile has a `ratio` chance to be selected
+    Noise(f32),
+    /// tiles not selected by the inner mask
+    Inverse(Box<MapMask>),
+}
+
+impl MapMask {
+    /// returns one flag per map tile (same order as [`Map::iter`])
+    pub fn flags(&self, rng: &mut StdRng, size: IVec2) -> Vec<bool> {
+        let mut flags = Vec::with_capacity((size.x * size.y) as usize);
+        if let MapMask::Inverse(inner) = self {
+            return inner.flags(rng, size).into_iter().map(|f| !f).collect();
+        }
+        for y in 0..size.y {
+            for x in 0..size.x {
+                let pt = IVec2::new(x, y);
+                flags.push(match self {
+                    MapMask::All => true,
+                    MapMask::Region(rect) => rect.contains(pt),
+                    MapMask::Left(ratio) => (x as f32) < size.x as f32 * ratio,
+                    MapMask::Bottom(ratio) => (y as f32) < size.y as f32 * ratio,
+                    // NOTE: a zero radius would divide into NaN
+                    MapMask::Center(ratio) if *ratio <= 0. => false,
+                    MapMask::Center(ratio) => {
+                        let radius = size.as_vec2() * *ratio / 2.;
+                        let delta = (pt.as_vec2() - (size - 1).as_vec2() / 2.) / radius;
+                        delta.length_squared() <= 1.
+                    }
+                    MapMask::Noise(ratio) => rng.gen::<f32>() < *ratio,
+                    MapMask::Inverse(_) => unreachable!(),
+                });
+            }
+        }
+        flags
+    }
+}
+
+enum PipelineStep {
+    Generate(Box<dyn MapGenerator>, MapMask),
+    Modify(Box<dyn MapModifier>, MapMask),
+}
+
+/// Chains generators and post processing passes into a single [`MapGenerator`].
+///
+/// ```ignore
+/// let generator = MapPipeline::new(RoomsGenerator::default())
+///     .then(DrunkardGenerator::default())
+///     .blend(ConwayLifeGenerator::default(), MapMask::Left(0.3))
+///     .then(ConwayLifeGenerator::default());
+/// ```
+#[derive(Default)]
+pub struct MapPipeline {
+    steps: Vec<PipelineStep>,
+}
+
+impl MapPipeline {
+    /// Starts the pipeline with a generator producing the base map.
+    pub fn new(generator: impl MapGenerator + 'static) -> Self {
+        Self::default().blend(generator, MapMask::All)
+    }
+
+    /// Applies the modifier on the whole map.
+    pub fn then(self, modifier: impl MapModifier + 'static) -> Self {
+        self.then_masked(modifier, MapMask::All)
+    }
+
+    /// Applies the modifier, but keeps only the changes selected by the mask.
+    pub fn then_masked(mut self, modifier: impl MapModifier + 'static, mask: MapMask) -> Self {
+        self.steps
+            .push(PipelineStep::Modify(Box::new(modifier), mask));
+        self
+    }
+
+    /// Generates a separate map and copies tiles selected by the mask on top of the current one.
+    pub fn blend(mut self, generator: impl MapGenerator + 'static, mask: MapMask) -> Self {
+        self.steps
+            .push(PipelineStep::Generate(Box::new(generator), mask));
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.steps.is_empty()
+    }
+}
+
+impl MapGenerator for MapPipeline {
+    fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
+        let mut map = Map::filled_with(size, Tile::Wall);
+        for step in self.steps.iter() {
+            match step {
+                PipelineStep::Generate(generator, mask) => {
+                    let generated = generator.gen(rng, size);
+                    let flags = mask.flags(rng, size);
+                    map.blend(&generated, &flags);
+                }
+                PipelineStep::Modify(modifier, MapMask::All) => {
+                    modifier.modify(rng, &mut map);
+                }
+                PipelineStep::Modify(modifier, mask) => {
+                    let mut modified = map.clone();
+                    modifier.modify(rng, &mut modified);
+                    let flags = mask.flags(rng, size);
+                    map.blend(&modified, &flags);
+                }
+            }
+        }
+        map
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::EmptyGenerator;
+
+    struct FillWith(Tile);
+
+    impl MapModifier for FillWith {
+        fn modify(&self, _rng: &mut StdRng, map: &mut Map) {
+            *map = Map::filled_with(map.size(), self.0);
+        }
+    }
+
+    impl MapGenerator for FillWith {
+        fn gen(&self, _rng: &mut StdRng, size: IVec2) -> Map {
+            Map::filled_with(size, self.0)
+        }
+    }
+
+    fn flags(mask: MapMask, size: IVec2) -> Vec<bool> {
+        mask.flags(&mut StdRng::seed_from_u64(1), size)
+    }
+
+    #[test]
+    fn mask_flags_follow_tile_order() {
+        let size = IVec2::new(4, 2);
+        assert_eq!(flags(MapMask::All, size), vec![true; 8]);
+        assert_eq!(
+            flags(MapMask::Left(0.5), size),
+            vec![true, true, false, false, true, true, false, false]
+        );
+        assert_eq!(
+            flags(MapMask::Bottom(0.5), size),
+            vec![true, true, true, true, false, false, false, false]
+        );
+        assert_eq!(
+            flags(
+                MapMask::Region(Rect::new(IVec2::new(1, 1), IVec2::new(2, 1))),
+                size
+            ),
+            vec![false, false, false, false, false, true, true, false]
+        );
+    }
+
+    #[test]
+    fn mask_inverse() {
+        let size = IVec2::new(5, 3);
+        let mask = MapMask::Center(0.5);
+        let inverse = MapMask::Inverse(Box::new(mask.clone()));
+        for (a, b) in flags(mask, size).iter().zip(flags(inverse, size)) {
+            assert_ne!(*a, b);
+        }
+    }
+
+    #[test]
+    fn mask_center_without_radius_selects_nothing() {
+        let size = IVec2::new(5, 3);
+        assert_eq!(flags(MapMask::Center(0.), size), vec![false; 15]);
+        assert_eq!(flags(MapMask::Center(-1.), size), vec![false; 15]);
+        let inverse = MapMask::Inverse(Box::new(MapMask::Center(0.)));
+        assert_eq!(flags(inverse, size), vec![true; 15]);
+        // NOTE: the center tile of an odd sized map is selected by any positive ratio
+        assert!(flags(MapMask::Center(0.01), size)[7]);
+    }
+
+    #[test]
+    fn pipeline_blends_and_masks_steps() {
+        let map = MapPipeline::new(EmptyGenerator {})
+            .then_masked(FillWith(Tile::Lava), MapMask::Left(0.5))
+            .blend(
+                FillWith(Tile::DeepWater),
+                MapMask::Region(Rect::new(IVec2::new(3, 1), IVec2::new(1, 1))),
+            )
+            .gen(&mut StdRng::seed_from_u64(1), IVec2::new(6, 3));
+        assert_eq!(map.to_string(), "&&&###\n&&&W.#\n&&&###\n");
+    }
+}
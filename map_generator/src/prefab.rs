@@ -0,0 +1,363 @@
+use super::prelude::*;
+use crate::MapParseError;
+use std::collections::VecDeque;
+
+const MONSTER_GLYPH: char = 'M';
+const ITEM_GLYPH: char = 'I';
+const KEEP_GLYPH: char = ' ';
+/// prefabs keep away from the map edge so their entrances can be connected
+const EDGE_MARGIN: i32 = 2;
+
+/// Hand authored room (treasure vault, shrine, boss arena...) stamped into a generated map.
+/// Rows go from top to bottom and use [`Tile::glyph`] characters plus
+/// `M` (monster spawn), `I` (item spawn) and ` ` (keeps the underlying tile).
+/// Passable tiles on the prefab border are entrances connected to the rest of the map.
+/// Rows may be of different widths, missing characters keep the underlying tile.
+/// Serde uses the name and rows (`(name: "shrine", rows: ["#.#", "#I#"])`) and rejects unknown glyphs.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(try_from = "PrefabRows", into = "PrefabRows")]
+pub struct Prefab {
+    pub name: String,
+    rows: Vec<String>,
+}
+
+/// Where a [`Prefab`] got stamped and its guaranteed spawns
+#[derive(Debug, Clone)]
+pub struct PrefabPlacement {
+    pub name: String,
+    pub rect: Rect,
+    pub monster_spawns: Vec<IVec2>,
+    pub item_spawns: Vec<IVec2>,
+    pub entrances: Vec<IVec2>,
+}
+
+impl Prefab {
+    /// reads rows of [`Tile::glyph`] and prefab characters. first row is the top of the prefab.
+    pub fn from_rows<S: AsRef<str>>(name: &str, rows: &[S]) -> Result<Self, MapParseError> {
+        if rows.is_empty() {
+            return Err(MapParseError::Empty);
+        }
+        for (row, line) in rows.iter().enumerate() {
+            for (column, glyph) in line.as_ref().chars().enumerate() {
+                let is_known = matches!(glyph, MONSTER_GLYPH | ITEM_GLYPH | KEEP_GLYPH)
+                    || Tile::from_glyph(glyph).is_some();
+                if !is_known {
+                    return Err(MapParseError::UnknownGlyph { glyph, row, column });
+                }
+            }
+        }
+        Ok(Self {
+            name: name.to_string(),
+            rows: rows.iter().map(|r| r.as_ref().to_string()).collect(),
+        })
+    }
+
+    /// uses the text (one line per map row) as prefab rows. errors point at lines of the text.
+    pub fn from_text(name: &str, text: &str) -> Result<Self, MapParseError> {
+        let (lines, rows): (Vec<_>, Vec<_>) = text
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .unzip();
+        Prefab::from_rows(name, &rows).map_err(|err| err.with_row(|row| lines[row]))
+    }
+
+    /// rows of the prefab, first row is the top one
+    pub fn rows(&self) -> &[String] {
+        &self.rows
+    }
+
+    pub fn size(&self) -> IVec2 {
+        IVec2::new(
+            self.rows
+                .iter()
+                .map(|r| r.chars().count())
+                .max()
+                .unwrap_or(0) as i32,
+            self.rows.len() as i32,
+        )
+    }
+
+    /// glyph at the prefab local point (bottom left is 0, 0)
+    fn glyph(&self, pt: IVec2) -> char {
+        let row = self.rows.len() as i32 - 1 - pt.y;
+        self.rows[row as usize]
+            .chars()
+            .nth(pt.x as usize)
+            .unwrap_or(KEEP_GLYPH)
+    }
+
+    /// copies the prefab into the map with its bottom left corner at `start`.
+    /// entrances are not connected (see [`PrefabStamper`]).
+    pub fn stamp(&self, map: &mut Map, start: IVec2) -> PrefabPlacement {
+        let size = self.size();
+        let mut placement = PrefabPlacement {
+            name: self.name.clone(),
+            rect: Rect::new(start, size),
+            monster_spawns: vec![],
+            item_spawns: vec![],
+            entrances: vec![],
+        };
+        placement.rect.for_each(|pt| {
+            if !map.is_in_bounds(pt) {
+                return;
+            }
+            let local = pt - start;
+            let tile = match self.glyph(local) {
+                KEEP_GLYPH => map[pt],
+                MONSTER_GLYPH => {
+                    placement.monster_spawns.push(pt);
+                    Tile::Floor
+                }
+                ITEM_GLYPH => {
+                    placement.item_spawns.push(pt);
+                    Tile::Floor
+                }
+                glyph => Tile::from_glyph(glyph).expect("prefab glyphs are checked when read"),
+            };
+            map[pt] = tile;
+            let is_border =
+                local.x == 0 || local.y == 0 || local.x == size.x - 1 || local.y == size.y - 1;
+            if is_border && tile.is_passable() {
+                placement.entrances.push(pt);
+            }
+        });
+        placement
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct PrefabRows {
+    name: String,
+    rows: Vec<String>,
+}
+
+impl TryFrom<PrefabRows> for Prefab {
+    type Error = MapParseError;
+
+    fn try_from(value: PrefabRows) -> Result<Self, Self::Error> {
+        Prefab::from_rows(&value.name, &value.rows)
+    }
+}
+
+impl From<Prefab> for PrefabRows {
+    fn from(prefab: Prefab) -> Self {
+        Self {
+            name: prefab.name,
+            rows: prefab.rows,
+        }
+    }
+}
+
+/// Stamps randomly chosen prefabs at random, non overlapping locations (preferably filled with walls)
+/// and carves corridors from their entrances to the closest passable tile outside.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PrefabStamper {
+    pub prefabs: Vec<Prefab>,
+    /// how many prefabs to stamp (less if they do not fit)
+    pub count: usize,
+}
+
+impl PrefabStamper {
+    pub fn stamp(&self, rng: &mut StdRng, map: &mut Map) -> Vec<PrefabPlacement> {
+        let mut placements: Vec<PrefabPlacement> = vec![];
+        for _ in 0..self.count {
+            let prefab = match self.prefabs.choose(rng) {
+                Some(prefab) => prefab,
+                None => break,
+            };
+            let max = map.size() - prefab.size() - IVec2::splat(EDGE_MARGIN);
+            if max.x < EDGE_MARGIN || max.y < EDGE_MARGIN {
+                continue;
+            }
+            // NOTE: solid rock is preferred, so the existing map stays connected.
+            // otherwise the map might need to be connected again (see `EnsureConnected`)
+            let mut solid = vec![];
+            let mut other = vec![];
+            for y in EDGE_MARGIN..=max.y {
+                for x in EDGE_MARGIN..=max.x {
+                    let rect = Rect::new(IVec2::new(x, y), prefab.size());
+                    if placements.iter().any(|p| p.rect.intersect_or_touch(rect)) {
+                        continue;
+                    }
+                    if is_solid(map, rect) {
+                        solid.push(rect.start);
+                    } else {
+                        other.push(rect.start);
+                    }
+                }
+            }
+            let candidates = if solid.is_empty() { other } else { solid };
+            if let Some(start) = candidates.choose(rng) {
+                placements.push(prefab.stamp(map, *start));
+                let rects: Vec<_> = placements.iter().map(|p| p.rect).collect();
+                for entrance in placements[placements.len() - 1].entrances.iter() {
+                    connect_entrance(map, *entrance, &rects);
+                }
+            }
+        }
+        placements
+    }
+}
+
+impl MapModifier for PrefabStamper {
+    fn modify(&self, rng: &mut StdRng, map: &mut Map) {
+        self.stamp(rng, map);
+    }
+}
+
+fn is_solid(map: &Map, rect: Rect) -> bool {
+    let mut is_solid = true;
+    rect.for_each(|pt| is_solid &= !map[pt].is_passable());
+    is_solid
+}
+
+/// breadth first search from the outside of the entrance (avoiding prefabs and map edges)
+/// until a passable tile is reached. carves the found path into floor.
+fn connect_entrance(map: &mut Map, entrance: IVec2, rects: &[Rect]) {
+    let is_open = |map: &Map, pt: IVec2| {
+        map.is_in_bounds(pt) && !map.is_edge(pt) && !rects.iter().any(|r| r.contains(pt))
+    };
+    let mut came_from: Vec<Option<IVec2>> = vec![None; map.iter().len()];
+    let mut visited = vec![false; map.iter().len()];
+    let mut queue = VecDeque::new();
+    for start in Map::get_wasd_neighbor_deltas().map(|d| entrance + d) {
+        if is_open(map, start) {
+            if map[start].is_passable() {
+                return;
+            }
+            visited[map.get_index(start)] = true;
+            queue.push_back(start);
+        }
+    }
+    while let Some(pt) = queue.pop_front() {
+        if map[pt].is_passable() {
+            let mut carve = came_from[map.get_index(pt)];
+            while let Some(c) = carve {
+                map[c] = Tile::Floor;
+                carve = came_from[map.get_index(c)];
+            }
+            return;
+        }
+        for nb in Map::get_wasd_neighbor_deltas().map(|d| pt + d) {
+            if !is_open(map, nb) || visited[map.get_index(nb)] {
+                continue;
+            }
+            visited[map.get_index(nb)] = true;
+            came_from[map.get_index(nb)] = Some(pt);
+            queue.push_back(nb);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::MapRegions;
+
+    #[test]
+    fn stamp_copies_tiles_and_spawns() {
+        // NOTE: the ragged middle row and the space keep the walls underneath
+        let prefab = Prefab::from_text("test", "#+##\nM I\n#..\n").unwrap();
+        assert_eq!(prefab.size(), IVec2::new(4, 3));
+        let mut map = Map::filled_with(IVec2::new(7, 5), Tile::Wall);
+
+        let placement = prefab.stamp(&mut map, IVec2::new(1, 1));
+
+        let expected: Map = "#######\n##+####\n#.#.###\n##..###\n#######\n"
+            .parse()
+            .unwrap();
+        assert_eq!(map, expected);
+        assert_eq!(
+            placement.rect,
+            Rect::new(IVec2::new(1, 1), IVec2::new(4, 3))
+        );
+        assert_eq!(placement.monster_spawns, vec![IVec2::new(1, 2)]);
+        assert_eq!(placement.item_spawns, vec![IVec2::new(3, 2)]);
+        assert_eq!(
+            placement.entrances,
+            vec![
+                IVec2::new(2, 1),
+                IVec2::new(3, 1),
+                IVec2::new(1, 2),
+                IVec2::new(2, 3),
+            ]
+        );
+    }
+
+    #[test]
+    fn kept_tiles_can_be_entrances() {
+        let prefab = Prefab::from_text("test", "###\n# #\n#.#\n").unwrap();
+        let mut map = Map::filled_with(IVec2::new(3, 3), Tile::Floor);
+
+        let placement = prefab.stamp(&mut map, IVec2::ZERO);
+
+        assert_eq!(map[IVec2::new(1, 1)], Tile::Floor);
+        assert_eq!(placement.entrances, vec![IVec2::new(1, 0)]);
+    }
+
+    #[test]
+    fn unknown_glyph_is_an_error() {
+        // NOTE: errors point at lines of the text, empty ones included
+        assert_eq!(
+            Prefab::from_text("typo", "\n#.#\n#x#\n").unwrap_err(),
+            MapParseError::UnknownGlyph {
+                glyph: 'x',
+                row: 2,
+                column: 1
+            }
+        );
+        assert_eq!(
+            Prefab::from_text("empty", "\n").unwrap_err(),
+            MapParseError::Empty
+        );
+        assert!(ron::from_str::<Prefab>(r##"(name: "typo", rows: ["#.", "#x"])"##).is_err());
+        let prefab: Prefab = ron::from_str(r##"(name: "ok", rows: ["#.", "MI"])"##).unwrap();
+        assert_eq!(prefab.rows(), ["#.", "MI"]);
+    }
+
+    #[test]
+    fn connect_entrance_carves_to_the_closest_floor() {
+        let mut map: Map = "#######\n#.....#\n#######\n#######\n###.###\n#######\n#######\n"
+            .parse()
+            .unwrap();
+        let prefab = Rect::new(IVec2::new(2, 1), IVec2::new(3, 2));
+
+        connect_entrance(&mut map, IVec2::new(3, 2), &[prefab]);
+
+        let expected: Map = "#######\n#.....#\n###.###\n###.###\n###.###\n#######\n#######\n"
+            .parse()
+            .unwrap();
+        assert_eq!(map, expected);
+    }
+
+    #[test]
+    fn stamper_prefers_solid_rock_away_from_the_edge() {
+        let mut original = Map::filled_with(IVec2::new(24, 14), Tile::Wall);
+        Rect::new(IVec2::ONE, IVec2::new(6, 12)).for_each(|pt| original[pt] = Tile::Floor);
+        let stamper = PrefabStamper {
+            prefabs: vec![Prefab::from_text("cell", "###\n#.#\n#+#\n").unwrap()],
+            count: 3,
+        };
+        for seed in 0..10 {
+            let mut map = original.clone();
+
+            let placements = stamper.stamp(&mut StdRng::seed_from_u64(seed), &mut map);
+
+            assert_eq!(placements.len(), 3);
+            for (i, placement) in placements.iter().enumerate() {
+                let rect = placement.rect;
+                assert!(rect.start.cmpge(IVec2::splat(EDGE_MARGIN)).all());
+                let end = rect.start + rect.size;
+                assert!(end.cmple(map.size() - IVec2::splat(EDGE_MARGIN)).all());
+                assert!(is_solid(&original, rect), "{:?} is not solid rock", rect);
+                for other in placements[i + 1..].iter() {
+                    assert!(!rect.intersect_or_touch(other.rect));
+                }
+            }
+            // NOTE: every entrance got connected to the floor that was there before
+            assert!(MapRegions::from_map(&map).is_connected());
+        }
+    }
+}
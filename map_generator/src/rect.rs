@@ -1,6 +1,7 @@
 use glam::IVec2;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
 pub struct Rect {
     pub start: IVec2,
     pub size: IVec2,
@@ -18,6 +19,13 @@ impl Rect {
         )
     }
 
+    pub fn contains(&self, pt: IVec2) -> bool {
+        pt.x >= self.start.x
+            && pt.x < self.start.x + self.size.x
+            && pt.y >= self.start.y
+            && pt.y < self.start.y + self.size.y
+    }
+
     pub fn intersect_or_touch(&self, rhs: Rect) -> bool {
         self.start.x <= rhs.start.x + rhs.size.x
             && self.start.x + self.size.x >= rhs.start.x
@@ -0,0 +1,253 @@
+use super::prelude::*;
+use std::collections::VecDeque;
+
+const DEFAULT_MIN_REGION_SIZE: usize = 8;
+
+/// Connected passable regions of a [`Map`]. Tiles are connected through up, down, left and right neighbors
+/// (the same way actors move).
+#[derive(Debug, Clone)]
+pub struct MapRegions {
+    size: IVec2,
+    labels: Vec<Option<usize>>,
+    sizes: Vec<usize>,
+}
+
+impl MapRegions {
+    /// labels every passable tile with a region index using flood fill
+    pub fn from_map(map: &Map) -> Self {
+        let mut labels = vec![None; map.iter().len()];
+        let mut sizes = Vec::new();
+        let mut queue = VecDeque::new();
+        for (start, tile) in map.enumerate() {
+            if !tile.is_passable() || labels[map.get_index(start)].is_some() {
+                continue;
+            }
+            let region = sizes.len();
+            let mut region_size = 0;
+            labels[map.get_index(start)] = Some(region);
+            queue.push_back(start);
+            while let Some(pt) = queue.pop_front() {
+                region_size += 1;
+                for nb in Map::get_wasd_neighbor_deltas().map(|d| pt + d) {
+                    if !map.is_in_bounds(nb) || !map[nb].is_passable() {
+                        continue;
+                    }
+                    let label = &mut labels[map.get_index(nb)];
+                    if label.is_none() {
+                        *label = Some(region);
+                        queue.push_back(nb);
+                    }
+                }
+            }
+            sizes.push(region_size);
+        }
+        Self {
+            size: map.size(),
+            labels,
+            sizes,
+        }
+    }
+
+    /// region index of the tile. `None` if the tile is impassable.
+    pub fn get(&self, pt: IVec2) -> Option<usize> {
+        if pt.x < 0 || pt.y < 0 || pt.x >= self.size.x || pt.y >= self.size.y {
+            return None;
+        }
+        self.labels[(pt.y * self.size.x + pt.x) as usize]
+    }
+
+    /// number of distinct regions
+    pub fn count(&self) -> usize {
+        self.sizes.len()
+    }
+
+    /// tile count of each region (indexed by region index)
+    pub fn sizes(&self) -> &[usize] {
+        &self.sizes
+    }
+
+    /// index of the region with the most tiles
+    pub fn largest(&self) -> Option<usize> {
+        self.sizes
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, s)| **s)
+            .map(|(r, _)| r)
+    }
+
+    /// true when every passable tile can be reached from any other floor tile
+    pub fn is_connected(&self) -> bool {
+        self.count() <= 1
+    }
+
+    /// positions of all tiles belonging to the region
+    pub fn points(&self, region: usize) -> impl Iterator<Item = IVec2> + '_ {
+        let width = self.size.x;
+        self.labels
+            .iter()
+            .enumerate()
+            .filter(move |(_, l)| **l == Some(region))
+            .map(move |(idx, _)| IVec2::new(idx as i32 % width, idx as i32 / width))
+    }
+}
+
+/// Guarantees a fully traversable map.
+/// Small orphan regions are filled with wall, the rest are joined with carved corridors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EnsureConnected {
+    /// regions with less tiles are filled with wall instead of being connected
+    pub min_region_size: usize,
+    /// when false every region except the largest one is filled with wall
+    pub carve_corridors: bool,
+}
+
+impl Default for EnsureConnected {
+    fn default() -> Self {
+        Self {
+            min_region_size: DEFAULT_MIN_REGION_SIZE,
+            carve_corridors: true,
+        }
+    }
+}
+
+impl MapModifier for EnsureConnected {
+    fn modify(&self, _rng: &mut StdRng, map: &mut Map) {
+        let regions = MapRegions::from_map(map);
+        let largest = if let Some(largest) = regions.largest() {
+            largest
+        } else {
+            return;
+        };
+        for (region, size) in regions.sizes().iter().enumerate() {
+            if region != largest && (!self.carve_corridors || *size < self.min_region_size) {
+                fill_region(map, &regions, region);
+            }
+        }
+        if !self.carve_corridors {
+            return;
+        }
+        loop {
+            let regions = MapRegions::from_map(map);
+            if regions.is_connected() {
+                return;
+            }
+            // NOTE: smallest first, so corridors lead into the bigger areas
+            let smallest = (0..regions.count())
+                .min_by_key(|r| regions.sizes()[*r])
+                .unwrap_or_default();
+            if !carve_to_closest_region(map, &regions, smallest) {
+                // NOTE: unreachable without crossing the map edge.
+                fill_region(map, &regions, smallest);
+            }
+        }
+    }
+}
+
+fn fill_region(map: &mut Map, regions: &MapRegions, region: usize) {
+    for pt in regions.points(region) {
+        map[pt] = Tile::Wall;
+    }
+}
+
+/// breadth first search through walls from every tile of the region
+/// until any other region is reached. carves the found path into floor.
+fn carve_to_closest_region(map: &mut Map, regions: &MapRegions, region: usize) -> bool {
+    let mut came_from: Vec<Option<IVec2>> = vec![None; map.iter().len()];
+    let mut visited = vec![false; map.iter().len()];
+    let mut queue = VecDeque::new();
+    for pt in regions.points(region) {
+        visited[map.get_index(pt)] = true;
+        queue.push_back(pt);
+    }
+    while let Some(pt) = queue.pop_front() {
+        for nb in Map::get_wasd_neighbor_deltas().map(|d| pt + d) {
+            if !map.is_in_bounds(nb) || map.is_edge(nb) || visited[map.get_index(nb)] {
+                continue;
+            }
+            visited[map.get_index(nb)] = true;
+            if regions.get(nb).is_some() {
+                let mut carve = Some(pt);
+                while let Some(c) = carve {
+                    if !map[c].is_passable() {
+                        map[c] = Tile::Floor;
+                    }
+                    carve = came_from[map.get_index(c)];
+                }
+                return true;
+            }
+            came_from[map.get_index(nb)] = Some(pt);
+            queue.push_back(nb);
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sorted(points: impl Iterator<Item = IVec2>) -> Vec<IVec2> {
+        let mut points: Vec<_> = points.collect();
+        points.sort_by_key(|p| (p.y, p.x));
+        points
+    }
+
+    #[test]
+    fn regions_are_labeled_by_flood_fill() {
+        let regions =
+            MapRegions::from_map(&"#######\n#..#..#\n#..#.##\n#######\n".parse().unwrap());
+        assert_eq!(regions.count(), 2);
+        assert_eq!(regions.sizes(), &[4, 3]);
+        assert_eq!(regions.largest(), Some(0));
+        assert!(!regions.is_connected());
+        assert_eq!(regions.get(IVec2::new(1, 1)), Some(0));
+        assert_eq!(regions.get(IVec2::new(5, 2)), Some(1));
+        assert_eq!(regions.get(IVec2::new(3, 1)), None);
+        assert_eq!(regions.get(IVec2::new(-1, 1)), None);
+        assert_eq!(
+            sorted(regions.points(1)),
+            vec![IVec2::new(4, 1), IVec2::new(4, 2), IVec2::new(5, 2)]
+        );
+    }
+
+    #[test]
+    fn diagonal_tiles_are_not_connected() {
+        let regions = MapRegions::from_map(&"####\n#.##\n##.#\n####\n".parse().unwrap());
+        assert_eq!(regions.count(), 2);
+    }
+
+    #[test]
+    fn ensure_connected_carves_corridors() {
+        let mut map: Map = "##########\n#...#....#\n#...#....#\n#...#....#\n##########\n"
+            .parse()
+            .unwrap();
+        EnsureConnected::default().modify(&mut StdRng::seed_from_u64(1), &mut map);
+        assert!(MapRegions::from_map(&map).is_connected());
+        assert_eq!(
+            map.iter().filter(|t| **t == Tile::Floor).count(),
+            9 + 12 + 1
+        );
+        assert!(map.get_edge().iter().all(|p| map[*p] == Tile::Wall));
+    }
+
+    #[test]
+    fn ensure_connected_fills_small_regions() {
+        let mut map: Map = "#########\n#.#.....#\n###.....#\n#########\n"
+            .parse()
+            .unwrap();
+        EnsureConnected::default().modify(&mut StdRng::seed_from_u64(1), &mut map);
+        assert_eq!(map[IVec2::new(1, 2)], Tile::Wall);
+        assert_eq!(MapRegions::from_map(&map).sizes(), &[10]);
+
+        let mut map: Map = "##########\n#...#....#\n#...#....#\n#...#....#\n##########\n"
+            .parse()
+            .unwrap();
+        EnsureConnected {
+            carve_corridors: false,
+            ..Default::default()
+        }
+        .modify(&mut StdRng::seed_from_u64(1), &mut map);
+        assert_eq!(MapRegions::from_map(&map).sizes(), &[12]);
+    }
+}
@@ -1,17 +1,22 @@
 use super::prelude::*;
+use super::Corridors;
 
 const DEFAULT_R0OM_SIZE_RATIO: f32 = 0.19;
 const DEFAULT_ROOM_COUNT_RATIO: f32 = 0.017;
 
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct RoomsGenerator {
     pub room_size_ratio: f32,
     pub room_count_ratio: f32,
+    pub corridors: Corridors,
 }
 impl Default for RoomsGenerator {
     fn default() -> Self {
         Self {
             room_size_ratio: DEFAULT_R0OM_SIZE_RATIO,
             room_count_ratio: DEFAULT_ROOM_COUNT_RATIO,
+            corridors: Corridors::default(),
         }
     }
 }
@@ -31,26 +36,12 @@ impl MapGenerator for RoomsGenerator {
                 }
             });
         }
-        carve_coriddors(&mut map, rooms);
+        self.corridors.carve(rng, &mut map, &rooms);
 
         map
     }
 }
 
-fn carve_coriddors(map: &mut Map, rooms: Vec<Rect>) {
-    // NOTE: huh.. we can do that in rust?
-    let mut rooms = rooms;
-    rooms.sort_by(|a, b| a.get_center().y.cmp(&b.get_center().y));
-    for (i, room) in rooms.iter().enumerate().skip(1) {
-        let prev = rooms[i - 1].get_center();
-        let new = room.get_center();
-        for pt in WalkGrid::new((prev.x, prev.y), (new.x, new.y)).map(|(x, y)| IVec2::new(x, y)) {
-            let tile = &mut map[pt];
-            *tile = Tile::Floor;
-        }
-    }
-}
-
 fn build_rooms(
     rng: &mut StdRng,
     map_size: IVec2,
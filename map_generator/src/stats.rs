@@ -0,0 +1,135 @@
+use super::prelude::*;
+use super::MapRegions;
+use std::fmt::Display;
+use std::ops::Range;
+use std::time::{Duration, Instant};
+
+/// Quality measures of a generated map
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct MapStats {
+    /// passable tiles out of all tiles
+    pub floor_ratio: f32,
+    /// connected passable regions (see [`MapRegions`])
+    pub region_count: usize,
+    /// tiles of the largest region out of all passable tiles
+    pub largest_region_share: f32,
+    pub dead_end_count: usize,
+}
+
+impl MapStats {
+    pub fn from_map(map: &Map) -> Self {
+        let floor_count = map.iter().filter(|t| t.is_passable()).count();
+        let regions = MapRegions::from_map(map);
+        let largest = regions
+            .largest()
+            .map(|r| regions.sizes()[r])
+            .unwrap_or_default();
+        Self {
+            floor_ratio: floor_count as f32 / map.iter().len() as f32,
+            region_count: regions.count(),
+            largest_region_share: if floor_count == 0 {
+                0.
+            } else {
+                largest as f32 / floor_count as f32
+            },
+            dead_end_count: map
+                .enumerate()
+                .filter(|(pt, _)| map.is_dead_end(*pt))
+                .count(),
+        }
+    }
+}
+
+/// Timings and [`MapStats`] of a generator run with many seeds at a single map size
+#[derive(Debug, Clone)]
+pub struct GeneratorReport {
+    pub name: String,
+    pub size: IVec2,
+    pub runs: usize,
+    pub time_min: Duration,
+    pub time_mean: Duration,
+    pub time_max: Duration,
+    pub floor_ratio_min: f32,
+    pub floor_ratio_mean: f32,
+    pub region_count_mean: f32,
+    pub region_count_max: usize,
+    pub largest_region_share_min: f32,
+    pub largest_region_share_mean: f32,
+    pub dead_end_count_mean: f32,
+}
+
+impl GeneratorReport {
+    /// generates a map with every seed (each with its own `StdRng`) and collects the stats
+    pub fn run(name: &str, generator: &dyn MapGenerator, size: IVec2, seeds: Range<u64>) -> Self {
+        let mut times = vec![];
+        let mut stats = vec![];
+        for seed in seeds {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let start = Instant::now();
+            let map = generator.gen(&mut rng, size);
+            times.push(start.elapsed());
+            stats.push(MapStats::from_map(&map));
+        }
+        let runs = stats.len();
+        let mean = |f: fn(&MapStats) -> f32| stats.iter().map(f).sum::<f32>() / runs.max(1) as f32;
+        let min = |f: fn(&MapStats) -> f32| stats.iter().map(f).reduce(f32::min).unwrap_or(0.);
+        Self {
+            name: name.to_string(),
+            size,
+            runs,
+            time_min: times.iter().min().copied().unwrap_or_default(),
+            time_mean: times.iter().sum::<Duration>() / runs.max(1) as u32,
+            time_max: times.iter().max().copied().unwrap_or_default(),
+            floor_ratio_min: min(|s| s.floor_ratio),
+            floor_ratio_mean: mean(|s| s.floor_ratio),
+            region_count_mean: mean(|s| s.region_count as f32),
+            region_count_max: stats.iter().map(|s| s.region_count).max().unwrap_or(0),
+            largest_region_share_min: min(|s| s.largest_region_share),
+            largest_region_share_mean: mean(|s| s.largest_region_share),
+            dead_end_count_mean: mean(|s| s.dead_end_count as f32),
+        }
+    }
+
+    /// column names matching the [`Display`] output
+    pub fn header() -> String {
+        format!(
+            "{:<16} {:>9} {:>5} {:>27} {:>11} {:>11} {:>11} {:>9} {:>13}",
+            "generator",
+            "size",
+            "runs",
+            "time ms (min/mean/max)",
+            "floor min",
+            "floor mean",
+            "regions",
+            "dead ends",
+            "largest share",
+        )
+    }
+}
+
+impl Display for GeneratorReport {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let ms = |d: Duration| d.as_secs_f64() * 1000.;
+        write!(
+            f,
+            "{:<16} {:>9} {:>5} {:>27} {:>11.3} {:>11.3} {:>11} {:>9.1} {:>13}",
+            self.name,
+            format!("{}x{}", self.size.x, self.size.y),
+            self.runs,
+            format!(
+                "{:.2}/{:.2}/{:.2}",
+                ms(self.time_min),
+                ms(self.time_mean),
+                ms(self.time_max)
+            ),
+            self.floor_ratio_min,
+            self.floor_ratio_mean,
+            format!("{:.1} ({})", self.region_count_mean, self.region_count_max),
+            self.dead_end_count_mean,
+            format!(
+                "{:.3}/{:.3}",
+                self.largest_region_share_min, self.largest_region_share_mean
+            ),
+        )
+    }
+}
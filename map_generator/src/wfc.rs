@@ -0,0 +1,441 @@
+use super::prelude::*;
+use crate::DrunkardGenerator;
+use std::collections::HashMap;
+
+const DEFAULT_PATTERN_SIZE: usize = 3;
+const DEFAULT_MAX_ATTEMPTS: usize = 10;
+const DEFAULT_SAMPLE: &str = "\
+################
+#....#.........#
+#....#.........#
+#..............#
+#....#.........#
+######.#####.###
+#......#.......#
+#.###..#..###..#
+#.###.....###..#
+#......#.......#
+####.#######.###
+#......#.......#
+#......#.......#
+#..............#
+#......#.......#
+################";
+
+/// right, up, left, down. opposite direction is `(d + 2) % 4`
+const DIRECTIONS: [IVec2; 4] = [
+    IVec2::new(1, 0),
+    IVec2::new(0, 1),
+    IVec2::new(-1, 0),
+    IVec2::new(0, -1),
+];
+
+/// Overlapping model wave function collapse.
+/// Synthesizes a map that locally looks like the hand authored sample.
+/// Falls back to a default [`DrunkardGenerator`] map when the sample can not be collapsed
+/// (see [`WfcGenerator::collapse`]), so a bad sample never leaves the map without floor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WfcGenerator {
+    /// sample rows from top to bottom. `#` is a wall, anything else is a floor.
+    /// defaults to a sample of small rooms joined by doorways
+    pub sample: Vec<String>,
+    /// width and height of the overlapping patterns
+    pub pattern_size: usize,
+    /// sample wraps around its edges when extracting patterns
+    pub periodic_sample: bool,
+    /// also use rotated and mirrored patterns
+    pub symmetry: bool,
+    /// restarts after a contradiction
+    pub max_attempts: usize,
+}
+
+impl Default for WfcGenerator {
+    fn default() -> Self {
+        Self::from_text(DEFAULT_SAMPLE)
+    }
+}
+
+impl WfcGenerator {
+    /// uses the text (one line per map row) as a sample
+    pub fn from_text(text: &str) -> Self {
+        Self {
+            sample: text
+                .lines()
+                .map(|l| l.trim_end().to_string())
+                .filter(|l| !l.is_empty())
+                .collect(),
+            pattern_size: DEFAULT_PATTERN_SIZE,
+            periodic_sample: true,
+            symmetry: true,
+            max_attempts: DEFAULT_MAX_ATTEMPTS,
+        }
+    }
+
+    fn sample_map(&self) -> Map {
+        let height = self.sample.len() as i32;
+        let width = self
+            .sample
+            .iter()
+            .map(|r| r.chars().count())
+            .max()
+            .unwrap_or(0) as i32;
+        let mut map = Map::filled_with(IVec2::new(width, height), Tile::Wall);
+        for (row, line) in self.sample.iter().enumerate() {
+            for (x, c) in line.chars().enumerate() {
+                // NOTE: first line is the top of the map
+                let pt = IVec2::new(x as i32, height - 1 - row as i32);
+                map[pt] = if c == '#' { Tile::Wall } else { Tile::Floor };
+            }
+        }
+        map
+    }
+
+    /// unique patterns with their weights (occurrence count)
+    fn patterns(&self, sample: &Map) -> (Vec<Vec<Tile>>, Vec<f64>) {
+        let n = self.pattern_size as i32;
+        let size = sample.size();
+        let (max_x, max_y) = if self.periodic_sample {
+            (size.x, size.y)
+        } else {
+            (size.x - n + 1, size.y - n + 1)
+        };
+        let mut patterns = Vec::new();
+        let mut weights = Vec::new();
+        let mut index = HashMap::new();
+        for y in 0..max_y {
+            for x in 0..max_x {
+                let mut pattern = Vec::with_capacity((n * n) as usize);
+                for dy in 0..n {
+                    for dx in 0..n {
+                        let pt = IVec2::new((x + dx) % size.x, (y + dy) % size.y);
+                        pattern.push(sample[pt]);
+                    }
+                }
+                let variants = if self.symmetry {
+                    symmetries(&pattern, n as usize)
+                } else {
+                    vec![pattern]
+                };
+                for variant in variants {
+                    let idx = *index.entry(variant.clone()).or_insert_with(|| {
+                        patterns.push(variant);
+                        weights.push(0.);
+                        patterns.len() - 1
+                    });
+                    weights[idx] += 1.;
+                }
+            }
+        }
+        (patterns, weights)
+    }
+}
+
+impl MapGenerator for WfcGenerator {
+    fn gen(&self, rng: &mut StdRng, size: IVec2) -> Map {
+        self.collapse(rng, size)
+            .unwrap_or_else(|| DrunkardGenerator::default().gen(rng, size))
+    }
+}
+
+impl WfcGenerator {
+    /// `None` if the sample is smaller than a pattern, every attempt ran into a contradiction
+    /// or the collapsed map has no floor at all.
+    pub fn collapse(&self, rng: &mut StdRng, size: IVec2) -> Option<Map> {
+        let mut map = Map::filled_with(size, Tile::Wall);
+        let sample = self.sample_map();
+        let n = self.pattern_size.max(1);
+        if sample.size().x < n as i32 || sample.size().y < n as i32 {
+            return None;
+        }
+        let (patterns, weights) = self.patterns(&sample);
+        let propagator = propagator(&patterns, n);
+
+        for _ in 0..self.max_attempts.max(1) {
+            let mut wave = Wave::new(size, &weights, &propagator);
+            if wave.run(rng, &propagator) {
+                for (idx, cell) in wave.observed().into_iter().enumerate() {
+                    let pt = map.get_point(idx);
+                    map[pt] = patterns[cell][0];
+                }
+                for pt in map.get_edge() {
+                    map[pt] = Tile::Wall;
+                }
+                return map.iter().any(|t| *t == Tile::Floor).then_some(map);
+            }
+        }
+        None
+    }
+}
+
+/// rotations and reflections of a square pattern
+fn symmetries(pattern: &[Tile], n: usize) -> Vec<Vec<Tile>> {
+    let rotate = |p: &[Tile]| -> Vec<Tile> {
+        (0..n * n)
+            .map(|i| {
+                let (x, y) = (i % n, i / n);
+                p[x * n + (n - 1 - y)]
+            })
+            .collect()
+    };
+    let reflect = |p: &[Tile]| -> Vec<Tile> {
+        (0..n * n)
+            .map(|i| {
+                let (x, y) = (i % n, i / n);
+                p[y * n + (n - 1 - x)]
+            })
+            .collect()
+    };
+    let mut variants = vec![pattern.to_vec()];
+    for i in 1..4 {
+        variants.push(rotate(&variants[i - 1]));
+    }
+    for i in 0..4 {
+        variants.push(reflect(&variants[i]));
+    }
+    variants
+}
+
+/// `propagator[d][a]` lists patterns that can be placed next to pattern `a` in direction `d`
+fn propagator(patterns: &[Vec<Tile>], n: usize) -> Vec<Vec<Vec<usize>>> {
+    let agrees = |a: &[Tile], b: &[Tile], d: IVec2| -> bool {
+        let n = n as i32;
+        for y in i32::max(0, d.y)..i32::min(n, d.y + n) {
+            for x in i32::max(0, d.x)..i32::min(n, d.x + n) {
+                if a[(y * n + x) as usize] != b[((y - d.y) * n + (x - d.x)) as usize] {
+                    return false;
+                }
+            }
+        }
+        true
+    };
+    DIRECTIONS
+        .iter()
+        .map(|d| {
+            patterns
+                .iter()
+                .map(|a| {
+                    (0..patterns.len())
+                        .filter(|b| agrees(a, &patterns[*b], *d))
+                        .collect()
+                })
+                .collect()
+        })
+        .collect()
+}
+
+/// possible patterns of every output cell
+struct Wave {
+    size: IVec2,
+    weights: Vec<f64>,
+    possible: Vec<Vec<bool>>,
+    /// `compatible[cell][pattern][d]` count of patterns still supporting `pattern` from direction `d`
+    compatible: Vec<Vec<[usize; 4]>>,
+    counts: Vec<usize>,
+    sum_weights: Vec<f64>,
+    sum_weight_logs: Vec<f64>,
+    stack: Vec<(usize, usize)>,
+}
+
+impl Wave {
+    fn new(size: IVec2, weights: &[f64], propagator: &[Vec<Vec<usize>>]) -> Self {
+        let cells = (size.x * size.y) as usize;
+        let patterns = weights.len();
+        let compatible_initial: Vec<[usize; 4]> = (0..patterns)
+            .map(|p| {
+                let mut c = [0; 4];
+                for (d, count) in c.iter_mut().enumerate() {
+                    *count = propagator[(d + 2) % 4][p].len();
+                }
+                c
+            })
+            .collect();
+        let sum_weights = weights.iter().sum::<f64>();
+        let sum_weight_logs = weights.iter().map(|w| w * w.ln()).sum::<f64>();
+        Self {
+            size,
+            weights: weights.to_vec(),
+            possible: vec![vec![true; patterns]; cells],
+            compatible: vec![compatible_initial; cells],
+            counts: vec![patterns; cells],
+            sum_weights: vec![sum_weights; cells],
+            sum_weight_logs: vec![sum_weight_logs; cells],
+            stack: Vec::new(),
+        }
+    }
+
+    /// returns false on contradiction
+    fn run(&mut self, rng: &mut StdRng, propagator: &[Vec<Vec<usize>>]) -> bool {
+        // NOTE: output wraps around, so patterns without a possible neighbor in some direction
+        // can not be placed anywhere
+        for cell in 0..self.counts.len() {
+            for p in 0..self.weights.len() {
+                if self.possible[cell][p] && self.compatible[cell][p].contains(&0) {
+                    self.ban(cell, p);
+                }
+            }
+            if self.counts[cell] == 0 {
+                return false;
+            }
+        }
+        if !self.propagate(propagator) {
+            return false;
+        }
+        loop {
+            match self.lowest_entropy_cell(rng) {
+                Err(()) => return false,
+                Ok(None) => return true,
+                Ok(Some(cell)) => {
+                    self.observe(rng, cell);
+                    if !self.propagate(propagator) {
+                        return false;
+                    }
+                }
+            }
+        }
+    }
+
+    /// `Ok(None)` when every cell is collapsed, `Err` on contradiction
+    fn lowest_entropy_cell(&self, rng: &mut StdRng) -> Result<Option<usize>, ()> {
+        let mut lowest = f64::MAX;
+        let mut cell = None;
+        for i in 0..self.counts.len() {
+            match self.counts[i] {
+                0 => return Err(()),
+                1 => continue,
+                _ => {
+                    let entropy = self.sum_weights[i].ln()
+                        - self.sum_weight_logs[i] / self.sum_weights[i]
+                        + rng.gen::<f64>() * 1e-6;
+                    if entropy < lowest {
+                        lowest = entropy;
+                        cell = Some(i);
+                    }
+                }
+            }
+        }
+        Ok(cell)
+    }
+
+    fn observe(&mut self, rng: &mut StdRng, cell: usize) {
+        let mut roll = rng.gen::<f64>() * self.sum_weights[cell];
+        let mut chosen = None;
+        for (p, possible) in self.possible[cell].iter().enumerate() {
+            if *possible {
+                chosen = Some(p);
+                roll -= self.weights[p];
+                if roll <= 0. {
+                    break;
+                }
+            }
+        }
+        for p in 0..self.weights.len() {
+            if self.possible[cell][p] && Some(p) != chosen {
+                self.ban(cell, p);
+            }
+        }
+    }
+
+    fn ban(&mut self, cell: usize, pattern: usize) {
+        self.possible[cell][pattern] = false;
+        self.compatible[cell][pattern] = [0; 4];
+        self.stack.push((cell, pattern));
+        let w = self.weights[pattern];
+        self.counts[cell] -= 1;
+        self.sum_weights[cell] -= w;
+        self.sum_weight_logs[cell] -= w * w.ln();
+    }
+
+    fn propagate(&mut self, propagator: &[Vec<Vec<usize>>]) -> bool {
+        while let Some((cell, pattern)) = self.stack.pop() {
+            let pt = IVec2::new(cell as i32 % self.size.x, cell as i32 / self.size.x);
+            for (d, delta) in DIRECTIONS.iter().enumerate() {
+                // NOTE: output wraps around, edges are turned into walls afterwards
+                let nb = pt + *delta;
+                let nb = IVec2::new(nb.x.rem_euclid(self.size.x), nb.y.rem_euclid(self.size.y));
+                let nb_cell = (nb.y * self.size.x + nb.x) as usize;
+                for other in propagator[d][pattern].iter() {
+                    let compatible = &mut self.compatible[nb_cell][*other][d];
+                    if *compatible == 0 {
+                        continue;
+                    }
+                    *compatible -= 1;
+                    if *compatible == 0 {
+                        self.ban(nb_cell, *other);
+                        if self.counts[nb_cell] == 0 {
+                            return false;
+                        }
+                    }
+                }
+            }
+        }
+        true
+    }
+
+    fn observed(&self) -> Vec<usize> {
+        self.possible
+            .iter()
+            .map(|p| p.iter().position(|b| *b).unwrap_or_default())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen(generator: &WfcGenerator, seed: u64) -> Map {
+        generator.gen(&mut StdRng::seed_from_u64(seed), IVec2::new(24, 18))
+    }
+
+    fn has_floor_within_walls(map: &Map) -> bool {
+        map.iter().any(|t| *t == Tile::Floor)
+            && map.get_edge().iter().all(|p| map[*p] == Tile::Wall)
+    }
+
+    #[test]
+    fn same_seed_same_map() {
+        let generator = WfcGenerator::default();
+        let map = gen(&generator, 3);
+        assert_eq!(map, gen(&generator, 3));
+        assert_ne!(map, gen(&generator, 4));
+        assert!(has_floor_within_walls(&map));
+    }
+
+    #[test]
+    fn default_sample_collapses() {
+        let generator = WfcGenerator::default();
+        let mut rng = StdRng::seed_from_u64(3);
+        assert!(generator.collapse(&mut rng, IVec2::new(24, 18)).is_some());
+    }
+
+    #[test]
+    fn sample_smaller_than_pattern_falls_back() {
+        let generator = WfcGenerator::from_text("#.\n.#\n");
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(generator.collapse(&mut rng, IVec2::new(24, 18)), None);
+        assert!(has_floor_within_walls(&gen(&generator, 3)));
+    }
+
+    #[test]
+    fn contradiction_falls_back() {
+        // NOTE: the only pattern does not match itself side by side
+        let generator = WfcGenerator {
+            pattern_size: 2,
+            periodic_sample: false,
+            symmetry: false,
+            ..WfcGenerator::from_text("#.\n.#\n")
+        };
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(generator.collapse(&mut rng, IVec2::new(24, 18)), None);
+        assert!(has_floor_within_walls(&gen(&generator, 3)));
+    }
+
+    #[test]
+    fn wall_only_sample_falls_back() {
+        let generator = WfcGenerator::from_text("####\n####\n####\n####\n");
+        let mut rng = StdRng::seed_from_u64(3);
+        assert_eq!(generator.collapse(&mut rng, IVec2::new(24, 18)), None);
+        assert!(has_floor_within_walls(&gen(&generator, 3)));
+    }
+}
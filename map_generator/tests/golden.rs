@@ -0,0 +1,71 @@
+//! Generated maps are compared with the golden ones in `tests/golden`.
+//! After an intended change of a generator rewrite them with
+//! `UPDATE_GOLDEN=1 cargo test -p map_generator --test golden` and review the diff.
+use glam::IVec2;
+use map_generator::*;
+use rand::prelude::*;
+use std::path::PathBuf;
+
+const SEED: u64 = 42;
+const SIZE: IVec2 = IVec2::new(48, 24);
+
+fn check(name: &str, generator: impl MapGenerator) {
+    let map = generator.gen(&mut StdRng::seed_from_u64(SEED), SIZE);
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.txt", name));
+    if std::env::var_os("UPDATE_GOLDEN").is_some() {
+        std::fs::write(&path, map.to_string()).unwrap();
+        return;
+    }
+    let text = std::fs::read_to_string(&path)
+        .unwrap_or_else(|err| panic!("could not read {}. {}", path.display(), err));
+    let golden: Map = text.parse().unwrap();
+    assert!(
+        golden == map,
+        "{} does not match {}. generated:\n{}",
+        name,
+        path.display(),
+        map
+    );
+}
+
+#[test]
+fn empty() {
+    check("empty", EmptyGenerator {});
+}
+
+#[test]
+fn drunkard() {
+    check("drunkard", DrunkardGenerator::default());
+}
+
+#[test]
+fn conway_life() {
+    check("conway_life", ConwayLifeGenerator::default());
+}
+
+#[test]
+fn rooms() {
+    check("rooms", RoomsGenerator::default());
+}
+
+#[test]
+fn bsp() {
+    check("bsp", BspGenerator::default());
+}
+
+#[test]
+fn wfc() {
+    check("wfc", WfcGenerator::default());
+}
+
+#[test]
+fn pipeline() {
+    check(
+        "pipeline",
+        MapPipeline::new(RoomsGenerator::default())
+            .then_masked(DrunkardGenerator::default(), MapMask::Left(0.5))
+            .then(EnsureConnected::default()),
+    );
+}
@@ -63,6 +63,11 @@ fn set_options(mut cmd: Commands) {
     cmd.insert_resource(MapOptions {
         map_size: IVec2::new(80, 50),
         tile_size: 32.0,
+        // NOTE: replay a run by its seed (shown in game and logged) with `ROGUE_SEED=<seed> cargo run`
+        seed: std::env::var("ROGUE_SEED")
+            .ok()
+            .and_then(|s| s.parse().ok()),
+        ..default()
     });
     cmd.insert_resource(InventoryDisplayOptions { tile_size: 32.0 })
 }